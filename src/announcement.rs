@@ -0,0 +1,59 @@
+use cubik::fonts::{FontText, LoadedFont, FontError, TextAlign};
+use cubik::glium::{Display, Program, Frame};
+use std::time::Instant;
+
+const TEXT_SIZE: f32 = 0.08;
+const DISPLAY_SECONDS: f32 = 6.;
+const MAX_ENTRIES: usize = 5;
+
+struct Announcement {
+	text: FontText,
+	created_time: Instant,
+	vertical_step: usize
+}
+
+pub struct Announcements {
+	entries: Vec<Announcement>
+}
+
+impl Announcements {
+	pub fn new() -> Self {
+		Self {
+			entries: Vec::new()
+		}
+	}
+
+	fn gen_position(vertical_step: usize) -> (f32, f32) {
+		(-1.75, 0.85 - (vertical_step as f32 * TEXT_SIZE))
+	}
+
+	pub fn push(&mut self, content: String, color: [f32; 4]) {
+		if self.entries.len() >= MAX_ENTRIES {
+			self.entries.remove(0);
+		}
+		let vertical_step = self.entries.len();
+		let mut text = FontText::new(content, TEXT_SIZE, Self::gen_position(vertical_step), TextAlign::Left);
+		text.ui_draw_info.color = color;
+		self.entries.push(Announcement {
+			text: text,
+			created_time: Instant::now(),
+			vertical_step: vertical_step
+		});
+	}
+
+	pub fn draw(&mut self, target: &mut Frame, display: &Display, program: &Program,
+		font: &LoadedFont) -> Result<(), FontError> {
+		self.entries.retain(|v| v.created_time.elapsed().as_secs_f32() < DISPLAY_SECONDS);
+
+		for (vertical_step, entry) in self.entries.iter_mut().enumerate() {
+			if entry.vertical_step != vertical_step {
+				entry.vertical_step = vertical_step;
+				entry.text.ui_draw_info.position = Self::gen_position(vertical_step);
+				entry.text.ui_draw_info.screen_dim = (0, 0);
+			}
+			entry.text.draw(target, display, program, font)?;
+		}
+
+		Ok(())
+	}
+}
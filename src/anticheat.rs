@@ -0,0 +1,184 @@
+use cubik::player::Player;
+use cubik::quadoctree::QuadOctreeNode;
+use cubik::collision::check_player_collision;
+use cubik::cube::generate_cube_collideobj;
+use cubik::math::{vector_length, normalize_vector};
+use serde::{Serialize, Deserialize};
+use std::collections::HashMap;
+use std::time::Instant;
+use log::{warn, debug};
+
+const DISTANCE_SLACK: f32 = 0.5;
+const WALL_PENETRATION_SLACK: f32 = 0.1;
+/// Seconds checks are skipped for after the server moved a player, while stale client positions arrive
+const RESET_GRACE_SECONDS: f32 = 1.;
+
+#[derive(Serialize, Deserialize, Clone)]
+#[serde(default)]
+pub struct AntiCheatSettings {
+	pub enabled: bool,
+	/// Multiplier applied to the player's move rate before movement is considered too fast
+	pub speed_tolerance: f32,
+	/// Maximum upward speed, in units per second
+	pub max_rise_speed: f32,
+	/// Violations before a player is kicked
+	pub max_violations: u32,
+	/// One violation is forgiven after this many seconds
+	pub violation_decay_seconds: f32
+}
+
+impl Default for AntiCheatSettings {
+	fn default() -> Self {
+		Self {
+			enabled: true,
+			speed_tolerance: 1.5,
+			max_rise_speed: 6.,
+			max_violations: 10,
+			violation_decay_seconds: 10.
+		}
+	}
+}
+
+struct MovementState {
+	last_check_time: Instant,
+	/// Simulated time since the last check, counted in server ticks so catch-up ticks
+	/// and bunched client messages don't shrink the allowance
+	time_since_check: f32,
+	time_since_decay: f32,
+	grace_time: f32,
+	violations: u32
+}
+
+impl MovementState {
+	fn new(grace_time: f32) -> Self {
+		Self {
+			last_check_time: Instant::now(),
+			time_since_check: 0.,
+			time_since_decay: 0.,
+			grace_time: grace_time,
+			violations: 0
+		}
+	}
+
+	/// The longer of wall-clock and simulated time, so neither stalls with dropped ticks
+	/// nor catch-up ticks make the allowance too small
+	fn elapsed(&self) -> f32 {
+		self.last_check_time.elapsed().as_secs_f32().max(self.time_since_check)
+	}
+}
+
+pub struct MovementValidator {
+	settings: AntiCheatSettings,
+	states: HashMap<u8, MovementState>
+}
+
+impl MovementValidator {
+	pub fn new(settings: AntiCheatSettings) -> Self {
+		Self {
+			settings: settings,
+			states: HashMap::new()
+		}
+	}
+
+	pub fn remove(&mut self, pid: u8) {
+		self.states.remove(&pid);
+	}
+
+	/// Call when the server moves a player itself, e.g. on respawn
+	pub fn reset(&mut self, pid: u8) {
+		if let Some(state) = self.states.get_mut(&pid) {
+			let violations = state.violations;
+			*state = MovementState::new(RESET_GRACE_SECONDS);
+			state.violations = violations;
+		}
+	}
+
+	pub fn reset_all(&mut self) {
+		let pids: Vec<u8> = self.states.keys().cloned().collect();
+		for pid in pids {
+			self.reset(pid);
+		}
+	}
+
+	/// Meant to be called once per server tick, before any movement is validated.
+	pub fn tick(&mut self, time_delta: f32) {
+		for state in self.states.values_mut() {
+			state.time_since_check += time_delta;
+			state.time_since_decay += time_delta;
+			state.grace_time = (state.grace_time - time_delta).max(0.);
+		}
+	}
+
+	fn find_violation(&self, previous_position: &[f32; 3], player: &Player, quadoctree: &QuadOctreeNode,
+		elapsed: f32) -> Option<String> {
+		let position = player.camera.position;
+
+		let horizontal_distance = vector_length(&[position[0] - previous_position[0], 0., position[2] - previous_position[2]]);
+		let max_distance = player.move_rate * self.settings.speed_tolerance * elapsed + DISTANCE_SLACK;
+		if horizontal_distance > max_distance {
+			return Some(format!("moved {:.2} units in {:.2}s (max {:.2})", horizontal_distance, elapsed, max_distance));
+		}
+
+		let rise = position[1] - previous_position[1];
+		let max_rise = self.settings.max_rise_speed * elapsed + DISTANCE_SLACK;
+		if rise > max_rise {
+			return Some(format!("rose {:.2} units in {:.2}s (max {:.2})", rise, elapsed, max_rise));
+		}
+
+		let collide_obj = generate_cube_collideobj(&[0., 0., 0.], &position, &[0.2, 0.2, 0.2], 0.);
+		let collide_result = check_player_collision(quadoctree, &position, &collide_obj);
+		for resolve in collide_result.polygons {
+			if normalize_vector(&resolve)[1] < 0.5 && vector_length(&resolve) > WALL_PENETRATION_SLACK {
+				return Some("moved inside map geometry".to_string());
+			}
+		}
+
+		None
+	}
+
+	/// Checks the movement a player made since `previous_position`.
+	/// Invalid movement is reverted, and a kick reason is returned once the player
+	/// exceeds the allowed violation count. The first movement of a player is only
+	/// used as a starting point, since there is no previous check to time it against.
+	/// Movement is also accepted for a moment after `reset`.
+	pub fn validate(&mut self, pid: u8, previous_position: [f32; 3], player: &mut Player,
+		quadoctree: &QuadOctreeNode) -> Option<String> {
+		if !self.settings.enabled { return None; }
+
+		let (elapsed, in_grace) = match self.states.get(&pid) {
+			Some(state) => (state.elapsed(), state.grace_time > 0.),
+			None => {
+				self.states.insert(pid, MovementState::new(0.));
+				return None;
+			}
+		};
+		let violation = if in_grace {
+			None
+		} else {
+			self.find_violation(&previous_position, player, quadoctree, elapsed)
+		};
+
+		let decay_seconds = self.settings.violation_decay_seconds;
+		let state = self.states.get_mut(&pid).unwrap();
+		state.last_check_time = Instant::now();
+		state.time_since_check = 0.;
+
+		if state.violations > 0 && state.time_since_decay > decay_seconds {
+			state.violations -= 1;
+			state.time_since_decay = 0.;
+		}
+
+		let reason = violation?;
+		player.camera.position = previous_position;
+		state.violations += 1;
+		state.time_since_decay = 0.;
+		debug!("movement violation by player {}: {} ({} violations)", pid, reason, state.violations);
+
+		if state.violations >= self.settings.max_violations {
+			warn!("kicking player {} after {} movement violations, last: {}", pid, state.violations, reason);
+			Some(reason)
+		} else {
+			None
+		}
+	}
+}
@@ -0,0 +1,33 @@
+use cubik::player::Player;
+
+/// Temporary multiplier on a player's move rate. The original rate is restored once it runs out.
+pub struct SpeedBoost {
+	base_move_rate: Option<f32>,
+	time_remaining: f32
+}
+
+impl SpeedBoost {
+	pub fn new() -> Self {
+		Self {
+			base_move_rate: None,
+			time_remaining: 0.
+		}
+	}
+
+	/// Restarts the boost if one is already running, without stacking multipliers
+	pub fn start(&mut self, player: &mut Player, multiplier: f32, seconds: f32) {
+		let base_move_rate = *self.base_move_rate.get_or_insert(player.move_rate);
+		player.move_rate = base_move_rate * multiplier;
+		self.time_remaining = seconds;
+	}
+
+	pub fn update(&mut self, player: &mut Player, time_delta: f32) {
+		if let Some(base_move_rate) = self.base_move_rate {
+			self.time_remaining -= time_delta;
+			if self.time_remaining <= 0. {
+				player.move_rate = base_move_rate;
+				self.base_move_rate = None;
+			}
+		}
+	}
+}
@@ -9,7 +9,9 @@ use std::panic::set_hook;
 use dirs::home_dir;
 use std::fs::File;
 use std::io::Write;
+use crate::logger::init_logger;
 use backtrace::Backtrace;
+use log::{error, warn};
 
 fn new_game(ctr: &mut RenderContainer, menu: &mut MainMenu, host: String, name: String, fps_count_enabled: bool) -> Option<GameClient> {
 	match GameClient::init(ctr, host, name, fps_count_enabled) {
@@ -19,7 +21,7 @@ fn new_game(ctr: &mut RenderContainer, menu: &mut MainMenu, host: String, name:
 		},
 		Err(e) => {
 			if let GameClientError::NetClientError(_) = e {
-				eprintln!("{:?}", e);
+				error!("failed to connect: {:?}", e);
 				menu.show_info_dialog("Failed to connect to server.".to_string());
 			} else {
 				panic!("{:?}", e);
@@ -47,7 +49,7 @@ pub fn init_panic_handler() {
 				loc.line()).as_str());
 		}
 		payload.push_str(format!("{:?}", backtrace).as_str());
-		println!("{}", payload);
+		error!("{}", payload);
 		let mut f = File::create(crash_dir).unwrap();
 		f.write_all(payload.as_bytes()).unwrap();
 	}));
@@ -56,6 +58,7 @@ pub fn init_panic_handler() {
 pub fn start_client(fullscreen: bool, host: Option<String>, username: Option<String>, fps_count_enabled: bool, input_switcher_enabled: bool) {
 	init_panic_handler();
 	let settings = Settings::load().unwrap();
+	init_logger(&settings.log).unwrap();
 
 	let event_loop = EventLoop::new();
 	let mut ctr = RenderContainer::new(&event_loop, settings.resolution[0], settings.resolution[1],
@@ -71,6 +74,24 @@ pub fn start_client(fullscreen: bool, host: Option<String>, username: Option<Str
 	};
 
 	event_loop.run(move |ev, _, control_flow| {
+		if let Event::WindowEvent { event: WindowEvent::KeyboardInput { input, .. }, .. } = &ev {
+			if input_enabled && input.virtual_keycode == Some(VirtualKeyCode::R) && input.state == ElementState::Released {
+				if let Some(g_client) = game_client.as_mut() {
+					g_client.toggle_ready().ok();
+				}
+			}
+			if input_enabled && input.virtual_keycode == Some(VirtualKeyCode::G) && input.state == ElementState::Released {
+				if let Some(g_client) = game_client.as_mut() {
+					g_client.drop_pack().ok();
+				}
+			}
+			if input_enabled && input.virtual_keycode == Some(VirtualKeyCode::F) && input.state == ElementState::Released {
+				if let Some(g_client) = game_client.as_mut() {
+					g_client.howl().ok();
+				}
+			}
+		}
+
 		let listeners: Vec<&mut dyn InputListener> = if let Some(game_client) = game_client.as_mut() {
 			vec![&mut game_client.player]
 		} else {
@@ -144,13 +165,26 @@ pub fn start_client(fullscreen: bool, host: Option<String>, username: Option<Str
 			},
 			Some(g_client) => {
 				// Game in progress
-				if let Err(e) = g_client.update(&mut target, &ctr) {
-					if let GameClientError::NetClientError(_) = e {
-						center_cursor(&ctr.display, true);
-						menu.show_info_dialog("Lost connection to server.".to_string());
-						game_client = None;
-					} else {
-						panic!("{:?}", e);
+				if let Err(e) = g_client.update(&mut target, &mut ctr) {
+					match e {
+						GameClientError::NetClientError(_) => {
+							warn!("lost connection to server: {:?}", e);
+							center_cursor(&ctr.display, true);
+							menu.show_info_dialog("Lost connection to server.".to_string());
+							game_client = None;
+						},
+						GameClientError::Kicked(reason) => {
+							warn!("kicked from server: {}", reason);
+							center_cursor(&ctr.display, true);
+							menu.show_info_dialog(format!("Kicked from server: {}", reason));
+							game_client = None;
+						},
+						GameClientError::MapNotFound(map_name) => {
+							center_cursor(&ctr.display, true);
+							menu.show_info_dialog(format!("Server map `{}` not found.", map_name));
+							game_client = None;
+						},
+						_ => panic!("{:?}", e)
 					}
 				}
 			}
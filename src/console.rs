@@ -0,0 +1,85 @@
+use std::io::{self, BufRead};
+use std::sync::mpsc::{channel, Receiver};
+use std::thread;
+use crate::stage::GameStage;
+
+pub enum ConsoleCommand {
+	Status,
+	Kick(u8),
+	Say(String),
+	Map(String),
+	Restart,
+	Stage(GameStage),
+	Packs
+}
+
+pub struct ServerConsole {
+	receiver: Receiver<ConsoleCommand>
+}
+
+impl ConsoleCommand {
+	fn parse(line: &str) -> Result<Self, String> {
+		let line = line.trim();
+		let (name, arg) = match line.find(' ') {
+			Some(i) => (&line[..i], line[i + 1..].trim()),
+			None => (line, "")
+		};
+		match name {
+			"status" => Ok(Self::Status),
+			"kick" => arg.parse().map(Self::Kick).map_err(|_| "usage: kick <pid>".to_string()),
+			"say" => {
+				if arg.is_empty() {
+					Err("usage: say <msg>".to_string())
+				} else {
+					Ok(Self::Say(arg.to_string()))
+				}
+			},
+			"map" => {
+				if arg.is_empty() {
+					Err("usage: map <name>".to_string())
+				} else {
+					Ok(Self::Map(arg.to_string()))
+				}
+			},
+			"restart" => Ok(Self::Restart),
+			"stage" => match arg {
+				"warmup" => Ok(Self::Stage(GameStage::Warmup)),
+				"start" => Ok(Self::Stage(GameStage::InProgress)),
+				"overtime" => Ok(Self::Stage(GameStage::Overtime)),
+				"finish" => Ok(Self::Stage(GameStage::Finished)),
+				_ => Err("usage: stage <warmup|start|overtime|finish>".to_string())
+			},
+			"packs" => Ok(Self::Packs),
+			_ => Err(format!("unknown command: {}", name))
+		}
+	}
+}
+
+impl ServerConsole {
+	pub fn start() -> Self {
+		let (sender, receiver) = channel();
+		thread::spawn(move || {
+			let stdin = io::stdin();
+			for line in stdin.lock().lines() {
+				let line = match line {
+					Ok(line) => line,
+					Err(_) => break
+				};
+				if line.trim().is_empty() { continue; }
+				match ConsoleCommand::parse(&line) {
+					Ok(cmd) => {
+						if sender.send(cmd).is_err() { break; }
+					},
+					Err(e) => println!("{}", e)
+				};
+			}
+		});
+		Self {
+			receiver: receiver
+		}
+	}
+
+	pub fn commands(&self) -> Vec<ConsoleCommand> {
+		self.receiver.try_iter().collect()
+	}
+}
@@ -1,6 +1,8 @@
 pub const APP_ID: &'static str = "wolfpack-game";
 pub const PORT: u16 = 27020;
 
+pub const DEFAULT_MAP: &'static str = "map3";
+
 pub const PLAYER_COLORS: [[f32; 3]; 6] = [
 	[1.0, 0.4, 0.4],
 	[1.0, 1.0, 0.4],
@@ -10,6 +12,19 @@ pub const PLAYER_COLORS: [[f32; 3]; 6] = [
 	[1.0, 0.4, 1.0]
 ];
 
+pub const TEAM_COLORS: [[f32; 3]; 4] = [
+	[1.0, 0.3, 0.3],
+	[0.3, 0.5, 1.0],
+	[0.3, 1.0, 0.3],
+	[1.0, 1.0, 0.3]
+];
+
+pub const TEAM_NAMES: [&'static str; 4] = ["Red", "Blue", "Green", "Yellow"];
+
 pub fn player_color(pid: u8) -> &'static [f32; 3] {
 	&PLAYER_COLORS[(pid as usize - 1) % 6]
 }
+
+pub fn map_path(name: &str) -> String {
+	format!("models/{}", name)
+}
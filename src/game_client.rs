@@ -4,11 +4,11 @@ use cubik::camera::perspective_matrix;
 use cubik::skybox::Skybox;
 use cubik::animation::{ObjAnimation, ObjAnimationError};
 use cubik::player::{Player, PlayerControlType};
-use cubik::fonts::FontError;
+use cubik::fonts::{FontError, FontText, TextAlign};
 use cubik::wavefront::WavefrontLoadError;
 use cubik::peer_player::PeerPlayer;
-use crate::constants::{APP_ID, PORT};
-use cubik::audio::{buffer_sound, get_sound_stream, SoundStream, AudioError};
+use crate::constants::{APP_ID, PORT, DEFAULT_MAP, map_path};
+use cubik::audio::{buffer_sound, get_sound_stream, play_sound_from_buffer, SoundStream, SoundData, AudioError};
 use cubik::fps_count::DebugFPSCounter;
 use cubik::container::RenderContainer;
 use cubik::math::mult_vector;
@@ -19,14 +19,36 @@ use cubik::client::{ClientContainer, ClientError};
 use cubik::map::{GameMap, GameMapError};
 use crate::msg::AppMessage;
 use crate::leaderboard::Leaderboard;
-use crate::minipack::MiniPacks;
-use crate::constants::player_color;
-use crate::stage::GameStageManager;
+use crate::minipack::{MiniPacks, PackKind};
+use crate::stage::{GameStageManager, GameStage, GameStageUpdate};
+use crate::announcement::Announcements;
+use crate::boost::SpeedBoost;
+use crate::powerup::{PowerUps, PowerUpEvent, PowerUpKind};
+use crate::render::{RenderError, ZoneRenderer, HillMarker, ProgressRing, PowerUpRenderer, Minimap};
 use std::time::Instant;
 use derive_more::{From, Error};
+use log::{info, warn};
 
 const FONT_SIZE: f32 = 80.;
 
+const SERVER_SAY_COLOR: [f32; 4] = [1.0, 1.0, 1.0, 1.0];
+const ELIMINATED_COLOR: [f32; 4] = [1.0, 0.25, 0.25, 1.0];
+const HILL_MOVED_COLOR: [f32; 4] = [1.0, 0.85, 0.2, 1.0];
+const OVERTIME_COLOR: [f32; 4] = [1.0, 0.5, 0.1, 1.0];
+const RESPAWN_COLOR: [f32; 4] = [0.5, 0.8, 1.0, 1.0];
+const POWERUP_COLOR: [f32; 4] = [0.6, 1.0, 0.6, 1.0];
+
+const HOWL_RING_POSITION: (f32, f32) = (0., -0.82);
+const HOWL_RING_RADIUS: f32 = 0.07;
+const HOWL_TEXT_SIZE: f32 = 0.06;
+const HOWL_READY_COLOR: [f32; 4] = [1.0, 0.85, 0.2, 0.9];
+const HOWL_CHARGING_COLOR: [f32; 4] = [0.6, 0.6, 0.6, 0.9];
+
+const STEAL_RING_POSITION: (f32, f32) = (0., -0.2);
+const STEAL_RING_RADIUS: f32 = 0.08;
+const STEALING_COLOR: [f32; 4] = [0.3, 1.0, 0.3, 0.9];
+const LOSING_COLOR: [f32; 4] = [1.0, 0.3, 0.3, 0.9];
+
 #[derive(From, Error, derive_more::Display, Debug)]
 pub enum GameClientError {
 	NetClientError(ClientError),
@@ -35,12 +57,22 @@ pub enum GameClientError {
 	GameMapError(GameMapError),
 	WavefrontError(WavefrontLoadError),
 	ObjAnimationError(ObjAnimationError),
-	SkyboxError(SkyboxError)
+	SkyboxError(SkyboxError),
+	RenderError(RenderError),
+	/// Reason given by the server
+	Kicked(#[error(not(source))] String),
+	/// Map the server switched to, which isn't installed locally
+	#[from(ignore)]
+	MapNotFound(#[error(not(source))] String)
 }
 
 pub struct GameClient {
 	map_info: ObjDrawInfo,
 	sound_stream: SoundStream,
+	howl_sound: SoundData,
+	/// Seconds until the local player can howl again
+	howl_cooldown: f32,
+	howl_text: FontText,
 
 	main_font: LoadedFont,
 
@@ -50,6 +82,8 @@ pub struct GameClient {
 	pub player: Player,
 
 	map: GameMap,
+	map_name: String,
+	pending_map_name: Option<String>,
 
 	packs: MiniPacks,
 
@@ -57,7 +91,7 @@ pub struct GameClient {
 	wolf_anim: ObjAnimation,
 	skybox: Skybox,
 
-	player_pack_counts: HashMap<u8, usize>,
+	player_wolf_counts: HashMap<u8, usize>,
 	leaderboard: Leaderboard,
 
 	lights_arr: [Light; MAX_LIGHTS],
@@ -65,28 +99,43 @@ pub struct GameClient {
 	last_frame_time: Instant,
 
 	game_stage_manager: GameStageManager,
+	announcements: Announcements,
+	zone_renderer: ZoneRenderer,
+	hill_marker: HillMarker,
+	progress_ring: ProgressRing,
+	speed_boost: SpeedBoost,
+	powerups: PowerUps,
+	powerup_renderer: PowerUpRenderer,
+	minimap: Minimap,
 
 	fps_count: DebugFPSCounter
 }
 
 impl GameClient {
 
+	fn load_map(ctr: &mut RenderContainer, map_name: &str) -> Result<(GameMap, [Light; MAX_LIGHTS]), GameClientError> {
+		let map = GameMap::load_map(&map_path(map_name), APP_ID, Some(&ctr.display), Some(&mut ctr.textures), true)?;
+
+		let mut lights_arr: [Light; MAX_LIGHTS] = Default::default();
+		let mut light_iter = map.lights.values();
+		for i in 0..map.lights.len() { lights_arr[i] = *light_iter.next().unwrap(); }
+
+		Ok((map, lights_arr))
+	}
+
 	pub fn init(ctr: &mut RenderContainer, host: String, username: String, fps_count_enabled: bool) -> Result<Self, GameClientError> {
 		let mut map_info: ObjDrawInfo = Default::default();
 		map_info.generate_matrix();
 
 		let mut client_container: ClientContainer<AppMessage> = ClientContainer::new(format!("{}:{}", host, PORT).as_str())?;
 		client_container.state_name(username)?;
+		info!("connected to {}:{}", host, PORT);
 		let mut player = Player::new([0.0, 1.5, 0.0], PlayerControlType::MultiplayerClient,
 			[0.0, 0.275, 0.0], [0.44, 0.275, 0.08]);
 
 		player.walking_sound = Some(buffer_sound("./audio/running.wav", APP_ID)?);
 
-		let map = GameMap::load_map("models/map3", APP_ID, Some(&ctr.display), Some(&mut ctr.textures), true)?;
-
-		let mut lights_arr: [Light; MAX_LIGHTS] = Default::default();
-		let mut light_iter = map.lights.values();
-		for i in 0..map.lights.len() { lights_arr[i] = *light_iter.next().unwrap(); }
+		let (map, lights_arr) = Self::load_map(ctr, DEFAULT_MAP)?;
 
 		Ok(Self {
 			map_info: map_info,
@@ -94,17 +143,31 @@ impl GameClient {
 			peer_map: HashMap::new(),
 			client_container: client_container,
 			sound_stream: get_sound_stream()?,
+			howl_sound: buffer_sound("./audio/howl.wav", APP_ID)?,
+			howl_cooldown: 0.,
+			howl_text: FontText::new("F".to_string(), HOWL_TEXT_SIZE,
+				(HOWL_RING_POSITION.0, HOWL_RING_POSITION.1 + HOWL_TEXT_SIZE / 2.), TextAlign::Center),
 			last_frame_time: Instant::now(),
 			player: player,
 			map: map,
+			map_name: DEFAULT_MAP.to_string(),
+			pending_map_name: None,
 			packs: MiniPacks::new(),
 			skybox: Skybox::new(&ctr.display, "skybox1", APP_ID, 512, 100.)?,
 
 			lights_arr: lights_arr,
-			player_pack_counts: HashMap::new(),
+			player_wolf_counts: HashMap::new(),
 			leaderboard: Leaderboard::new(),
 
-			game_stage_manager: GameStageManager::new(),
+			game_stage_manager: GameStageManager::new(Default::default()),
+			announcements: Announcements::new(),
+			zone_renderer: ZoneRenderer::new(&ctr.display)?,
+			hill_marker: HillMarker::new(),
+			progress_ring: ProgressRing::new(&ctr.display)?,
+			speed_boost: SpeedBoost::new(),
+			powerups: PowerUps::new(),
+			powerup_renderer: PowerUpRenderer::new(&ctr.display)?,
+			minimap: Minimap::new(&ctr.display)?,
 
 			wolf_standing: cubik::wavefront::load_obj("models/wolf_standing.obj", APP_ID, Some(&ctr.display), Some(&mut ctr.textures),
 				&[1., 1., 1.], None, None, None)?,
@@ -126,21 +189,112 @@ impl GameClient {
 					if self.client_container.player_id.unwrap_or(0) == player_id {
 						self.player.update(0., None, Some(&self.sound_stream), Some(msg));
 					} else {
+						let color = mult_vector(&self.game_stage_manager.teams.color(player_id), 10.);
 						let peer_player = self.peer_map.entry(player_id)
 							.or_insert_with(|| {
 								let mut r = PeerPlayer::new();
-								r.obj_draw_info.color = mult_vector(player_color(player_id), 10.);
+								r.obj_draw_info.color = color;
 								r
 							});
 
 						peer_player.update(Some(msg), time_delta);
 					}
 				},
+				AppMessage::Kicked { player_id, reason } => {
+					if self.client_container.player_id == Some(player_id) {
+						return Err(GameClientError::Kicked(reason));
+					}
+				},
 				AppMessage::PackUpdate(_) => {
-					self.packs.client_update_msg(msg);
+					for (pid, kind) in self.packs.client_update_msg(msg) {
+						if kind == PackKind::Speed && self.client_container.player_id == Some(pid) {
+							let variants = &self.game_stage_manager.rules.variants;
+							self.speed_boost.start(&mut self.player, variants.speed_multiplier, variants.speed_seconds);
+						}
+					}
 				},
 				AppMessage::StageChange(update) => {
-					self.game_stage_manager.client_update(update, &self.map, &mut self.packs);
+					let match_state = match &update {
+						GameStageUpdate::InProgress { eliminated, hill, .. } => Some((eliminated, hill)),
+						GameStageUpdate::Overtime { eliminated, hill, .. } => Some((eliminated, hill)),
+						_ => None
+					};
+					if let Some((eliminated, hill)) = match_state {
+						for pid in eliminated {
+							if self.game_stage_manager.is_eliminated(*pid) { continue; }
+							let name = self.client_container.peers.get(pid).and_then(|p| p.name.clone()).unwrap_or_default();
+							self.announcements.push(format!("{} was eliminated", name), ELIMINATED_COLOR);
+						}
+						let hill_moved = match (self.game_stage_manager.hill.as_ref(), hill.as_ref()) {
+							(Some(previous), Some(hill)) => previous.center != hill.center,
+							_ => false
+						};
+						if hill_moved {
+							self.announcements.push("The hill has moved".to_string(), HILL_MOVED_COLOR);
+						}
+					}
+					match (&self.game_stage_manager.current_stage, &update) {
+						(GameStage::InProgress, GameStageUpdate::Overtime { .. }) => {
+							self.announcements.push("Overtime! Sudden death".to_string(), OVERTIME_COLOR);
+						},
+						(GameStage::Overtime, GameStageUpdate::Finished { winner: None, .. }) => {
+							self.announcements.push("Overtime ended in a tie".to_string(), OVERTIME_COLOR);
+						},
+						_ => ()
+					};
+					self.game_stage_manager.client_update(update, &self.map, &mut self.packs, &self.client_container.peers,
+						self.client_container.player_id);
+					match self.game_stage_manager.current_stage {
+						GameStage::InProgress | GameStage::Overtime => {
+							if !self.powerups.is_spawned() {
+								self.powerups.spawn(&self.map);
+							}
+						},
+						_ => self.powerups.clear()
+					};
+				},
+				AppMessage::PowerUp(event) => {
+					if let PowerUpEvent::PickedUp { pid, kind, .. } = &event {
+						if self.client_container.player_id == Some(*pid) {
+							self.announcements.push(format!("{}!", kind.name()), POWERUP_COLOR);
+							if *kind == PowerUpKind::SpeedBurst {
+								let rules = &self.game_stage_manager.rules.powerups;
+								self.speed_boost.start(&mut self.player, rules.speed_multiplier, rules.speed_seconds);
+							}
+						}
+					}
+					self.powerups.client_event(event);
+				},
+				AppMessage::PowerUpSnapshot(snapshot) => {
+					self.powerups.client_snapshot(snapshot);
+				},
+				AppMessage::ServerSay(text) => {
+					self.announcements.push(format!("Server: {}", text), SERVER_SAY_COLOR);
+				},
+				AppMessage::RulesChange(rules) => {
+					self.game_stage_manager.rules = rules;
+				},
+				AppMessage::ReadyToggle | AppMessage::DropPack | AppMessage::Howl => (),
+				AppMessage::Howled(pid) => {
+					if let Err(e) = play_sound_from_buffer(&self.sound_stream, &self.howl_sound) {
+						warn!("failed to play howl sound: {:?}", e);
+					}
+					if self.client_container.player_id == Some(pid) {
+						self.howl_cooldown = self.game_stage_manager.rules.howl.cooldown_seconds;
+					}
+				},
+				AppMessage::PacksRespawned(count) => {
+					let content = if count == 1 { "A new pack has appeared".to_string() } else { format!("{} new packs have appeared", count) };
+					self.announcements.push(content, RESPAWN_COLOR);
+				},
+				AppMessage::TeamsChange(teams) => {
+					self.game_stage_manager.teams = teams;
+				},
+				AppMessage::MapChange(map_name) => {
+					if map_name != self.map_name {
+						info!("server changed map to {}", map_name);
+						self.pending_map_name = Some(map_name);
+					}
 				}
 			}
 		}
@@ -159,21 +313,56 @@ impl GameClient {
 		Ok(())
 	}
 
-	pub fn update(&mut self, target: &mut Frame, ctr: &RenderContainer) -> Result<(), GameClientError> {
+	pub fn toggle_ready(&mut self) -> Result<(), GameClientError> {
+		self.client_container.send(AppMessage::ReadyToggle)?;
+		Ok(())
+	}
+
+	pub fn drop_pack(&mut self) -> Result<(), GameClientError> {
+		self.client_container.send(AppMessage::DropPack)?;
+		Ok(())
+	}
+
+	pub fn howl(&mut self) -> Result<(), GameClientError> {
+		self.client_container.send(AppMessage::Howl)?;
+		Ok(())
+	}
+
+	pub fn update(&mut self, target: &mut Frame, ctr: &mut RenderContainer) -> Result<(), GameClientError> {
 		let new_frame_time = Instant::now();
 		let time_delta = new_frame_time.duration_since(self.last_frame_time).as_secs_f32();
 		self.last_frame_time = new_frame_time;
 
 		self.net_update(time_delta)?;
 
-		self.player_pack_counts.clear();
+		if let Some(map_name) = self.pending_map_name.take() {
+			let (map, lights_arr) = Self::load_map(ctr, &map_name).map_err(|e| {
+				warn!("failed to load server map {}: {:?}", map_name, e);
+				GameClientError::MapNotFound(map_name.clone())
+			})?;
+			self.map = map;
+			self.lights_arr = lights_arr;
+			self.map_name = map_name;
+			self.packs.packs.clear();
+			self.powerups.clear();
+		}
+
+		self.speed_boost.update(&mut self.player, time_delta);
+
+		self.player_wolf_counts.clear();
 		for pack in &mut self.packs.packs {
-			pack.client_update(self.map.quadoctree.as_ref().unwrap(), time_delta);
+			pack.client_update(self.map.quadoctree.as_ref().unwrap(), time_delta, &self.game_stage_manager.teams);
 			if let Some(pid) = pack.owner {
-				self.player_pack_counts.insert(pid, self.player_pack_counts.get(&pid).unwrap_or(&0) + 1);
+				*self.player_wolf_counts.entry(pid).or_insert(0) += pack.wolves();
 			}
 		}
 
+		self.zone_renderer.update(self.game_stage_manager.zone.as_ref(), &self.player.camera.position, time_delta);
+		self.hill_marker.update(self.game_stage_manager.hill.as_ref(), &self.player.camera.position);
+		self.powerups.client_update(time_delta);
+		self.howl_cooldown = (self.howl_cooldown - time_delta).max(0.);
+		self.powerup_renderer.update(time_delta);
+
 		let perspective_mat = perspective_matrix(target);
 		let env_info = EnvDrawInfo {
 			perspective_mat: perspective_mat,
@@ -190,7 +379,9 @@ impl GameClient {
 			basic_render(target, &env_info, &self.map_info, &o, &ctr.main_program, None);
 		}
 
-		for peer_player in self.peer_map.values_mut() {
+		for (pid, peer_player) in self.peer_map.iter_mut() {
+			if self.game_stage_manager.is_eliminated(*pid) { continue; }
+			peer_player.obj_draw_info.color = mult_vector(&self.game_stage_manager.teams.color(*pid), 10.);
 			peer_player.draw(target, &env_info, &ctr.main_program, &self.wolf_anim, 
 				&self.wolf_standing, self.wolf_anim.get_keyframe_by_index(5));
 		}
@@ -204,11 +395,64 @@ impl GameClient {
 
 		self.skybox.draw(target, &env_info, &ctr.skybox_program);
 
+		self.powerup_renderer.draw_world(target, &env_info, &self.powerups)?;
+
+		self.zone_renderer.draw_wall(target, &env_info, self.game_stage_manager.zone.as_ref())?;
+
+		let scores = if self.game_stage_manager.rules.mode.uses_points() {
+			Some(&self.game_stage_manager.scores)
+		} else {
+			None
+		};
 		self.leaderboard.draw(target, &ctr.display, &ctr.ui_program, &self.main_font, &self.client_container.peers,
-			&self.player_pack_counts).unwrap();
+			&self.player_wolf_counts, &self.game_stage_manager.teams, scores).unwrap();
 
 		self.game_stage_manager.draw(target, &ctr.display, &ctr.ui_program, &self.main_font).unwrap();
 
+		self.zone_renderer.draw_hud(target, &ctr.display, &ctr.ui_program, &self.main_font).unwrap();
+
+		self.hill_marker.draw(target, &ctr.display, &ctr.ui_program, &self.main_font, self.game_stage_manager.hill.as_ref(),
+			&env_info.view_mat, &env_info.perspective_mat).unwrap();
+
+		// show the steal in progress that involves this player the most
+		let player_id = self.client_container.player_id;
+		let steal_progress = self.packs.packs.iter().filter_map(|pack| {
+			let (stealer, progress) = pack.steal?;
+			if player_id == Some(stealer) {
+				Some((progress, STEALING_COLOR))
+			} else if player_id.is_some() && pack.owner == player_id {
+				Some((progress, LOSING_COLOR))
+			} else {
+				None
+			}
+		}).max_by(|a, b| a.0.partial_cmp(&b.0).unwrap());
+		if let Some((progress, color)) = steal_progress {
+			self.progress_ring.draw(target, STEAL_RING_POSITION, STEAL_RING_RADIUS, progress, color)?;
+		}
+
+		let reveal = player_id.map(|pid| self.powerups.is_active(pid, PowerUpKind::Reveal)).unwrap_or(false);
+		self.minimap.draw(target, &ctr.display, &self.player.camera.position, player_id, &self.packs,
+			&self.game_stage_manager.teams, reveal)?;
+		self.powerup_renderer.draw_hud(target, &ctr.display, &ctr.ui_program, &self.main_font, &self.progress_ring,
+			&self.powerups, player_id)?;
+
+		let howl_rules = &self.game_stage_manager.rules.howl;
+		let in_game = match self.game_stage_manager.current_stage {
+			GameStage::InProgress | GameStage::Overtime => true,
+			_ => false
+		};
+		if howl_rules.enabled && in_game && player_id.map(|pid| !self.game_stage_manager.is_eliminated(pid)).unwrap_or(false) {
+			let (progress, color) = if self.howl_cooldown > 0. && howl_rules.cooldown_seconds > 0. {
+				(1. - self.howl_cooldown / howl_rules.cooldown_seconds, HOWL_CHARGING_COLOR)
+			} else {
+				(1., HOWL_READY_COLOR)
+			};
+			self.progress_ring.draw(target, HOWL_RING_POSITION, HOWL_RING_RADIUS, progress, color)?;
+			self.howl_text.draw(target, &ctr.display, &ctr.ui_program, &self.main_font).unwrap();
+		}
+
+		self.announcements.draw(target, &ctr.display, &ctr.ui_program, &self.main_font).unwrap();
+
 		self.fps_count.update();
 
 		Ok(())
@@ -2,31 +2,38 @@ use cubik::fonts::{LoadedFont, FontText, FontError, TextAlign};
 use cubik::glium::{Display, Program, Frame};
 use cubik::client::PeerMeta;
 use cubik::math::mult_vector;
-use crate::minipack::PACK_SIZE;
-use crate::constants::player_color;
+use crate::team::Teams;
 use std::collections::HashMap;
 
 const TEXT_SIZE: f32 = 0.125;
 
+#[derive(Hash, PartialEq, Eq, Clone, Copy)]
+enum LeaderboardKey {
+	Team(u8),
+	Player(u8)
+}
+
 struct LeaderboardEntry {
 	text: FontText,
-	count: usize,
+	content: String,
+	color: [f32; 3],
 	vertical_step: usize
 }
 
 impl LeaderboardEntry {
-	fn new(pid: u8, peer: &PeerMeta, player_pack_count: usize, vertical_step: usize) -> Self {
+	fn new(content: String, color: [f32; 3], vertical_step: usize) -> Self {
 		let mut text = FontText::new(
-			format!("{}: {}", peer.name.as_ref().unwrap(), (player_pack_count * PACK_SIZE).to_string()),
+			content.clone(),
 			TEXT_SIZE,
 			Self::gen_position(vertical_step),
 			TextAlign::Right
 		);
-		let color = mult_vector(player_color(pid), 0.9);
-		text.ui_draw_info.color = [color[0], color[1], color[2], 1.];
+		let draw_color = mult_vector(&color, 0.9);
+		text.ui_draw_info.color = [draw_color[0], draw_color[1], draw_color[2], 1.];
 		Self {
 			text: text,
-			count: player_pack_count,
+			content: content,
+			color: color,
 			vertical_step: vertical_step
 		}
 	}
@@ -43,7 +50,7 @@ impl LeaderboardEntry {
 }
 
 pub struct Leaderboard {
-	entries: HashMap<u8, LeaderboardEntry>
+	entries: HashMap<LeaderboardKey, LeaderboardEntry>
 }
 
 impl Leaderboard {
@@ -53,38 +60,67 @@ impl Leaderboard {
 		}
 	}
 
+	fn format_value(value: usize, uses_points: bool) -> String {
+		if uses_points {
+			format!("{} pts", value)
+		} else {
+			value.to_string()
+		}
+	}
+
+	fn player_rows(pids: &mut Vec<u8>, peers: &HashMap<u8, PeerMeta>, values: &HashMap<u8, usize>, uses_points: bool,
+		teams: &Teams, rows: &mut Vec<(LeaderboardKey, String, [f32; 3])>) {
+		pids.sort_by(|a, b| values.get(b).unwrap_or(&0).cmp(&values.get(a).unwrap_or(&0)));
+		for pid in pids.iter() {
+			if let Some(name) = peers.get(pid).and_then(|peer| peer.name.as_ref()) {
+				let value = Self::format_value(*values.get(pid).unwrap_or(&0), uses_points);
+				rows.push((LeaderboardKey::Player(*pid), format!("{}: {}", name, value), teams.color(*pid)));
+			}
+		}
+	}
+
+	/// Shows mode points when `scores` is given, and wolf counts otherwise.
 	pub fn draw(&mut self, target: &mut Frame, display: &Display, program: &Program, font: &LoadedFont,
-		peers: &HashMap<u8, PeerMeta>, player_pack_counts: &HashMap<u8, usize>) -> Result<(), FontError> {
-		if peers.len() < self.entries.len() {
-			self.entries.clear();
+		peers: &HashMap<u8, PeerMeta>, player_wolf_counts: &HashMap<u8, usize>, teams: &Teams,
+		scores: Option<&HashMap<u8, u32>>) -> Result<(), FontError> {
+		let uses_points = scores.is_some();
+		let values: HashMap<u8, usize> = match scores {
+			Some(scores) => scores.iter().map(|(pid, score)| (*pid, *score as usize)).collect(),
+			None => player_wolf_counts.clone()
+		};
+		let team_value = |team: u8| -> usize {
+			teams.members(team).iter().map(|pid| values.get(pid).unwrap_or(&0)).sum()
+		};
+
+		let mut rows = Vec::new();
+		if teams.enabled() {
+			let mut sorted_teams: Vec<u8> = (0..teams.team_count()).collect();
+			sorted_teams.sort_by_key(|team| std::cmp::Reverse(team_value(*team)));
+			for team in sorted_teams {
+				let mut members: Vec<u8> = teams.members(team).into_iter().filter(|pid| peers.contains_key(pid)).collect();
+				if members.is_empty() { continue; }
+				rows.push((LeaderboardKey::Team(team), format!("Team {}: {}", Teams::team_name(team),
+					Self::format_value(team_value(team), uses_points)), teams.color(members[0])));
+				Self::player_rows(&mut members, peers, &values, uses_points, teams, &mut rows);
+			}
+		} else {
+			let mut pids: Vec<u8> = peers.keys().cloned().collect();
+			Self::player_rows(&mut pids, peers, &values, uses_points, teams, &mut rows);
 		}
 
-		let mut sorted_pids: Vec<u8> = peers.keys().cloned().collect();
-		sorted_pids.sort_by(|a, b| {
-			player_pack_counts.get(b).unwrap_or(&0).cmp(&player_pack_counts.get(a).unwrap_or(&0))
-		});
-
-		let mut vertical_step = 0;
-		for pid in sorted_pids {
-			if let Some(peer) = peers.get(&pid) {
-				let player_pack_count = player_pack_counts.get(&pid).unwrap_or(&0);
-
-				if peer.name.is_none() { continue; }
-				
-				if let Some(existing) = self.entries.get_mut(&pid) {
-					if existing.count == *player_pack_count {
-						if existing.vertical_step != vertical_step {
-							existing.update_position(vertical_step);
-						}
-						vertical_step += 1;
-						continue;
+		self.entries.retain(|key, _| rows.iter().any(|(row_key, _, _)| row_key == key));
+
+		for (vertical_step, (key, content, color)) in rows.into_iter().enumerate() {
+			if let Some(existing) = self.entries.get_mut(&key) {
+				if existing.content == content && existing.color == color {
+					if existing.vertical_step != vertical_step {
+						existing.update_position(vertical_step);
 					}
+					continue;
 				}
-
-				self.entries.insert(pid, LeaderboardEntry::new(pid, peer, *player_pack_count, vertical_step));
-
-				vertical_step += 1;
 			}
+
+			self.entries.insert(key, LeaderboardEntry::new(content, color, vertical_step));
 		}
 
 		for entry in self.entries.values_mut() {
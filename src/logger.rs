@@ -0,0 +1,158 @@
+use log::{Log, Metadata, Record, LevelFilter, SetLoggerError};
+use derive_more::{From, Error, Display};
+use serde::{Serialize, Deserialize};
+use std::fs::{self, File, OpenOptions};
+use std::io::{self, Write};
+use std::path::PathBuf;
+use std::sync::Mutex;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+#[derive(From, Error, Display, Debug)]
+pub enum LoggerError {
+	IOError(io::Error),
+	SetLoggerError(SetLoggerError)
+}
+
+#[derive(Serialize, Deserialize, Clone)]
+#[serde(default)]
+pub struct LogSettings {
+	pub level: LevelFilter,
+	pub json: bool,
+	pub file: Option<PathBuf>,
+	pub max_file_size: u64,
+	pub max_files: usize
+}
+
+impl Default for LogSettings {
+	fn default() -> Self {
+		Self {
+			level: LevelFilter::Info,
+			json: false,
+			file: None,
+			max_file_size: 10 * 1024 * 1024,
+			max_files: 5
+		}
+	}
+}
+
+#[derive(Serialize)]
+struct JsonLine<'a> {
+	time: f64,
+	level: &'a str,
+	target: &'a str,
+	msg: String
+}
+
+struct LogFile {
+	path: PathBuf,
+	file: File,
+	size: u64,
+	max_size: u64,
+	max_files: usize
+}
+
+impl LogFile {
+	fn open(path: PathBuf, max_size: u64, max_files: usize) -> io::Result<Self> {
+		let file = OpenOptions::new().create(true).append(true).open(&path)?;
+		let size = file.metadata()?.len();
+		Ok(Self {
+			path: path,
+			file: file,
+			size: size,
+			max_size: max_size,
+			max_files: max_files
+		})
+	}
+
+	fn rotated_path(&self, index: usize) -> PathBuf {
+		let mut path = self.path.clone().into_os_string();
+		path.push(format!(".{}", index));
+		path.into()
+	}
+
+	fn rotate(&mut self) -> io::Result<()> {
+		// shift log.1 -> log.2 etc., the oldest file gets overwritten
+		for i in (1..self.max_files).rev() {
+			let src = if i == 1 { self.path.clone() } else { self.rotated_path(i - 1) };
+			if src.exists() {
+				fs::rename(src, self.rotated_path(i))?;
+			}
+		}
+		self.file = File::create(&self.path)?;
+		self.size = 0;
+		Ok(())
+	}
+
+	fn write_line(&mut self, line: &str) -> io::Result<()> {
+		let len = line.len() as u64 + 1;
+		if self.size > 0 && self.size + len > self.max_size {
+			self.rotate()?;
+		}
+		writeln!(self.file, "{}", line)?;
+		self.size += len;
+		Ok(())
+	}
+}
+
+struct Logger {
+	level: LevelFilter,
+	json: bool,
+	file: Option<Mutex<LogFile>>
+}
+
+impl Logger {
+	fn format(&self, record: &Record) -> String {
+		let time = SystemTime::now().duration_since(UNIX_EPOCH).map(|v| v.as_secs_f64()).unwrap_or(0.);
+		if self.json {
+			serde_json::to_string(&JsonLine {
+				time: time,
+				level: record.level().as_str(),
+				target: record.target(),
+				msg: record.args().to_string()
+			}).unwrap_or_default()
+		} else {
+			format!("[{:.3} {} {}] {}", time, record.level(), record.target(), record.args())
+		}
+	}
+}
+
+impl Log for Logger {
+	fn enabled(&self, metadata: &Metadata) -> bool {
+		metadata.level() <= self.level
+	}
+
+	fn log(&self, record: &Record) {
+		if !self.enabled(record.metadata()) { return; }
+		let line = self.format(record);
+		eprintln!("{}", line);
+		if let Some(file) = self.file.as_ref() {
+			if let Ok(mut file) = file.lock() {
+				if let Err(e) = file.write_line(&line) {
+					eprintln!("failed to write to log file: {}", e);
+				}
+			}
+		}
+	}
+
+	fn flush(&self) {
+		if let Some(file) = self.file.as_ref() {
+			if let Ok(mut file) = file.lock() {
+				file.file.flush().ok();
+			}
+		}
+	}
+}
+
+pub fn init_logger(settings: &LogSettings) -> Result<(), LoggerError> {
+	let file = match settings.file.as_ref() {
+		Some(path) => Some(Mutex::new(LogFile::open(path.clone(), settings.max_file_size, settings.max_files)?)),
+		None => None
+	};
+	log::set_boxed_logger(Box::new(Logger {
+		level: settings.level,
+		json: settings.json,
+		file: file
+	}))?;
+	log::set_max_level(settings.level);
+	Ok(())
+}
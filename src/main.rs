@@ -8,6 +8,23 @@ mod leaderboard;
 mod stage;
 mod menu;
 mod settings;
+mod console;
+mod announcement;
+mod logger;
+mod server_settings;
+mod metrics;
+mod tick;
+mod anticheat;
+mod rules;
+mod results;
+mod zone;
+mod team;
+mod mode;
+mod series;
+mod nav;
+mod boost;
+mod powerup;
+mod render;
 
 use std::env;
 
@@ -33,7 +33,6 @@ impl SettingsDialog {
 			0
 		};
 		Ok(Self {
-			settings: settings,
 			bg: ImageBackground::new(display, "./textures/dialog_lite.png", APP_ID, (0., 0.), (1.3, 0.84))?,
 			title: FontText::new("Settings".to_string(), 0.07, (-0.6, 0.32), TextAlign::Left),
 			resolution_label: FontText::new("Resolution:".to_string(), 0.07, (-0.5, 0.132), TextAlign::Left),
@@ -55,6 +54,7 @@ impl SettingsDialog {
 			apply_btn: TextButton::new("Apply".to_string(), 0.065, (0.5, -0.35), (0.10, 0.05), NORMAL_COLOR, HOVER_COLOR),
 
 			cancel_btn: TextButton::new("Cancel".to_string(), 0.065, (0.25, -0.35), (0.10, 0.05), NORMAL_COLOR, HOVER_COLOR),
+			settings: settings,
 			enabled: false,
 			result: None
 		})
@@ -119,7 +119,7 @@ impl InputListener for SettingsDialog {
 			return true;
 		}
 		if self.apply_btn.handle_mouse_ev(mouse_button, state) {
-			self.result = Some(MenuResult::SettingsChange(self.settings));
+			self.result = Some(MenuResult::SettingsChange(self.settings.clone()));
 			return true;
 		}
 		if self.cancel_btn.handle_mouse_ev(mouse_button, state) {
@@ -0,0 +1,132 @@
+use std::collections::BTreeMap;
+use std::fmt::Write as FmtWrite;
+use std::io::{self, Read, Write};
+use std::net::{TcpListener, TcpStream};
+use std::sync::{Arc, Mutex};
+use std::thread;
+use std::time::Duration;
+use log::{info, warn};
+use crate::msg::AppMessage;
+use crate::stage::{GameStage, GameStageUpdate};
+
+const REQUEST_TIMEOUT_SECONDS: u64 = 5;
+const TICK_BUCKETS: [f64; 9] = [0.001, 0.002, 0.005, 0.01, 0.017, 0.025, 0.05, 0.1, 0.25];
+
+pub type SharedMetrics = Arc<Mutex<ServerMetrics>>;
+
+pub struct ServerMetrics {
+	pub player_count: usize,
+	pub stage: String,
+	tick_bucket_counts: [u64; TICK_BUCKETS.len()],
+	tick_sum: f64,
+	tick_count: u64,
+	messages_sent: BTreeMap<&'static str, u64>,
+	messages_received: BTreeMap<&'static str, u64>,
+	bytes_sent: u64,
+	matches_played: u64
+}
+
+impl ServerMetrics {
+	pub fn new() -> Self {
+		Self {
+			player_count: 0,
+			stage: format!("{:?}", GameStage::Standby),
+			tick_bucket_counts: [0; TICK_BUCKETS.len()],
+			tick_sum: 0.,
+			tick_count: 0,
+			messages_sent: BTreeMap::new(),
+			messages_received: BTreeMap::new(),
+			bytes_sent: 0,
+			matches_played: 0
+		}
+	}
+
+	pub fn shared() -> SharedMetrics {
+		Arc::new(Mutex::new(Self::new()))
+	}
+
+	pub fn record_tick(&mut self, seconds: f64) {
+		for (i, bucket) in TICK_BUCKETS.iter().enumerate() {
+			if seconds <= *bucket {
+				self.tick_bucket_counts[i] += 1;
+			}
+		}
+		self.tick_sum += seconds;
+		self.tick_count += 1;
+	}
+
+	pub fn record_sent(&mut self, msg: &AppMessage, recipients: usize) {
+		*self.messages_sent.entry(msg.name()).or_insert(0) += recipients as u64;
+		// cubik encodes messages with bincode, so this matches the payload size on the wire
+		self.bytes_sent += bincode::serialized_size(msg).unwrap_or(0) * recipients as u64;
+		// a series is only one match once its last round is over
+		if let AppMessage::StageChange(GameStageUpdate::Finished { series, .. }) = msg {
+			if series.as_ref().map(|s| s.match_over).unwrap_or(true) {
+				self.matches_played += 1;
+			}
+		}
+	}
+
+	pub fn record_received(&mut self, msg: &AppMessage) {
+		*self.messages_received.entry(msg.name()).or_insert(0) += 1;
+	}
+
+	fn render(&self) -> String {
+		let mut out = String::new();
+		writeln!(out, "# TYPE wolfpack_players gauge").ok();
+		writeln!(out, "wolfpack_players {}", self.player_count).ok();
+		writeln!(out, "# TYPE wolfpack_stage gauge").ok();
+		writeln!(out, "wolfpack_stage{{stage=\"{}\"}} 1", self.stage).ok();
+		writeln!(out, "# TYPE wolfpack_tick_duration_seconds histogram").ok();
+		for (i, bucket) in TICK_BUCKETS.iter().enumerate() {
+			writeln!(out, "wolfpack_tick_duration_seconds_bucket{{le=\"{}\"}} {}", bucket, self.tick_bucket_counts[i]).ok();
+		}
+		writeln!(out, "wolfpack_tick_duration_seconds_bucket{{le=\"+Inf\"}} {}", self.tick_count).ok();
+		writeln!(out, "wolfpack_tick_duration_seconds_sum {}", self.tick_sum).ok();
+		writeln!(out, "wolfpack_tick_duration_seconds_count {}", self.tick_count).ok();
+		writeln!(out, "# TYPE wolfpack_messages_sent_total counter").ok();
+		for (name, count) in &self.messages_sent {
+			writeln!(out, "wolfpack_messages_sent_total{{type=\"{}\"}} {}", name, count).ok();
+		}
+		writeln!(out, "# TYPE wolfpack_messages_received_total counter").ok();
+		for (name, count) in &self.messages_received {
+			writeln!(out, "wolfpack_messages_received_total{{type=\"{}\"}} {}", name, count).ok();
+		}
+		writeln!(out, "# TYPE wolfpack_bytes_sent_total counter").ok();
+		writeln!(out, "wolfpack_bytes_sent_total {}", self.bytes_sent).ok();
+		writeln!(out, "# TYPE wolfpack_matches_played_total counter").ok();
+		writeln!(out, "wolfpack_matches_played_total {}", self.matches_played).ok();
+		out
+	}
+}
+
+fn handle_request(mut stream: TcpStream, metrics: &SharedMetrics) -> io::Result<()> {
+	// a client that never sends its request would otherwise block the endpoint forever
+	stream.set_read_timeout(Some(Duration::from_secs(REQUEST_TIMEOUT_SECONDS)))?;
+	let mut buf = [0u8; 1024];
+	let len = stream.read(&mut buf)?;
+	let request = String::from_utf8_lossy(&buf[..len]);
+	let path = request.split_whitespace().nth(1).unwrap_or("");
+
+	let (status, body) = if path == "/metrics" {
+		("200 OK", metrics.lock().unwrap().render())
+	} else {
+		("404 Not Found", String::new())
+	};
+	write!(stream, "HTTP/1.1 {}\r\nContent-Type: text/plain; version=0.0.4\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}",
+		status, body.len(), body)
+}
+
+pub fn start_metrics_server(address: &str, metrics: SharedMetrics) -> io::Result<()> {
+	let listener = TcpListener::bind(address)?;
+	info!("metrics endpoint listening on http://{}/metrics", address);
+	thread::spawn(move || {
+		for stream in listener.incoming() {
+			let result = stream.and_then(|stream| handle_request(stream, &metrics));
+			if let Err(e) = result {
+				warn!("metrics request failed: {}", e);
+			}
+		}
+	});
+	Ok(())
+}
@@ -5,30 +5,73 @@ use cubik::animation::ObjAnimation;
 use cubik::quadoctree::QuadOctreeNode;
 use cubik::collision::check_player_collision;
 use cubik::draw::{ObjDrawInfo, EnvDrawInfo, ObjDef, basic_render};
-use cubik::math::{vector_length, add_vector, normalize_vector, mult_vector};
+use cubik::math::{vector_length, add_vector, normalize_vector};
 use cubik::cube::generate_cube_collideobj;
 use cubik::interpolation::{Interpolate, InterpolationHelper};
 use crate::msg::AppMessage;
-use crate::constants::player_color;
-use std::collections::{BTreeMap, HashMap};
-use rand::Rng;
+use crate::rules::{MatchRules, RespawnRules, VariantRules, HowlRules};
+use crate::team::Teams;
+use crate::mode::GameMode;
+use crate::nav::{NavGrid, advance_along};
+use std::collections::{BTreeMap, HashMap, HashSet};
+use rand::{Rng, SeedableRng};
+use rand::rngs::StdRng;
 use serde::{Serialize, Deserialize};
+use log::info;
 
 const SPAWN_PREFIX: &str = "misc_minipack_spawn_";
 pub const PACK_SIZE: usize = 5;
+const ALPHA_PACK_WOLVES: usize = 10;
+const ALPHA_SCALE: f32 = 1.3;
+const NEUTRAL_COLOR: [f32; 3] = [1.0, 1.0, 1.0];
+const SPEED_TINT: [f32; 3] = [0.5, 0.8, 1.0];
+const DECOY_TINT: [f32; 3] = [0.75, 0.65, 0.55];
 const POSITION_VARIANCE: f32 = 1.25;
 const YAW_VARIANCE: f32 = 2.;
 const MIN_MEMBER_DISTANCE: f32 = 0.3;
 const COLLIDE_CHECK_DECR: f32 = 0.002;
 
-const PICKUP_DISTANCE: f32 = 2.;
-const STARTING_FOLLOW_DISTANCE: f32 = 2.;
-const FOLLOW_DISTANCE_INCR: f32 = 1.;
-
-const MAX_MOVING_DROP_COUNT: u8 = 15;
+const MOVING_DROP_SECONDS: f32 = 0.25;
 
 const SERVER_UPDATE_INTERVAL: f32 = 0.1;
 
+const SCATTER_MIN_DISTANCE: f32 = 4.;
+const SCATTER_MAX_DISTANCE: f32 = 8.;
+const SCATTER_ATTEMPTS: usize = 10;
+
+const WANDER_LEASH_RADIUS: f32 = 6.;
+const WANDER_SPEED: f32 = 0.8;
+const WANDER_MIN_WAIT: f32 = 1.;
+const WANDER_MAX_WAIT: f32 = 4.;
+const WANDER_ARRIVE_DISTANCE: f32 = 0.2;
+const FLEE_DISTANCE: f32 = 5.;
+const FLEE_SPEED: f32 = 2.2;
+const FLEE_LEASH_RADIUS: f32 = 12.;
+const TURN_RATE: f32 = 4.;
+const HOWL_PUSH_SPEED: f32 = 4.;
+const REPATH_DISTANCE: f32 = 1.;
+/// Minimum seconds between path searches of a pack
+const REPATH_INTERVAL: f32 = 0.5;
+
+/// Chain index of a newly gained pack, so it is appended to the end of the owner's chain
+const NEW_CHAIN_INDEX: usize = usize::MAX;
+
+/// Random positions tried per pack when a wave runs out of free spawn points
+const RESPAWN_POSITION_ATTEMPTS: usize = 30;
+/// Spawn points closer than this to an existing neutral pack are skipped
+const RESPAWN_PACK_SPACING: f32 = 3.;
+
+const FLOCK_COHESION: f32 = 0.8;
+const FLOCK_SEPARATION: f32 = 1.5;
+const FLOCK_SEPARATION_DISTANCE: f32 = 0.8;
+const FLOCK_ALIGNMENT: f32 = 1.2;
+const FLOCK_FOLLOW: f32 = 4.;
+const FLOCK_DAMPING: f32 = 2.5;
+const FLOCK_MAX_SPEED: f32 = 7.;
+const FLOCK_SNAP_DISTANCE: f32 = 8.;
+const MEMBER_MOVING_SPEED: f32 = 0.4;
+const ANIM_PHASE_VARIANCE: f32 = 1.;
+
 #[derive(Copy, Clone)]
 struct PosYawValue {
 	pos: (f32, f32),
@@ -56,16 +99,96 @@ impl Interpolate for PosYawValue {
 	}
 }
 
+/// Yaw facing along a movement direction, in the range expected by `PosYawValue` interpolation
+fn direction_yaw(x: f32, z: f32) -> f32 {
+	let yaw = (z / x).atan();
+	if x < 0. {
+		yaw - std::f32::consts::PI
+	} else {
+		yaw
+	}
+}
+
+#[derive(Serialize, Deserialize, Clone, Copy, PartialEq, Debug)]
+pub enum PackKind {
+	Normal,
+	/// Worth more wolves, drawn larger
+	Alpha,
+	/// Gives its new owner a temporary speed boost
+	Speed,
+	/// Scatters when a player tries to capture it, and is a normal pack afterwards
+	Decoy
+}
+
+impl PackKind {
+	fn random(rules: &VariantRules) -> Self {
+		let roll = rand::thread_rng().gen_range(0.0..1.0);
+		if roll < rules.alpha_chance {
+			PackKind::Alpha
+		} else if roll < rules.alpha_chance + rules.speed_chance {
+			PackKind::Speed
+		} else if roll < rules.alpha_chance + rules.speed_chance + rules.decoy_chance {
+			PackKind::Decoy
+		} else {
+			PackKind::Normal
+		}
+	}
+
+	/// Colour of the members while the pack is neutral
+	fn tint(&self) -> [f32; 3] {
+		match self {
+			PackKind::Speed => SPEED_TINT,
+			PackKind::Decoy => DECOY_TINT,
+			_ => NEUTRAL_COLOR
+		}
+	}
+
+	fn scale(&self) -> f32 {
+		match self {
+			PackKind::Alpha => ALPHA_SCALE,
+			_ => 1.
+		}
+	}
+}
+
+/// Random walkable point a few units away, used when packs scatter.
+/// Stays in place if no walkable point was found.
+fn scatter_position(position: (f32, f32), nav: &NavGrid) -> (f32, f32) {
+	let mut rng = rand::thread_rng();
+	for _ in 0..SCATTER_ATTEMPTS {
+		let angle = rng.gen_range(0.0..(std::f32::consts::PI * 2.));
+		let distance = rng.gen_range(SCATTER_MIN_DISTANCE..SCATTER_MAX_DISTANCE);
+		let candidate = (position.0 + angle.cos() * distance, position.1 + angle.sin() * distance);
+		if nav.is_walkable(candidate) {
+			return candidate;
+		}
+	}
+	position
+}
+
+/// A wolf in a pack, flocking around its slot next to the pack centre
 #[derive(Default)]
 pub struct PackMember {
 	pos_offset: (f32, f32),
-	standing_yaw: f32,
+	position: (f32, f32),
+	velocity: (f32, f32),
+	yaw: f32,
+	anim_phase: f32,
 	draw_info: ObjDrawInfo
 }
 
+impl PackMember {
+	fn is_moving(&self) -> bool {
+		(self.velocity.0 * self.velocity.0 + self.velocity.1 * self.velocity.1).sqrt() > MEMBER_MOVING_SPEED
+	}
+}
+
 pub struct MiniPacks {
 	pub packs: Vec<MiniPack>,
-	net_update_time_count: f32
+	net_update_time_count: f32,
+	/// Height of packs that weren't placed at a spawn point
+	height: f32,
+	wave_cooldown: f32
 }
 
 pub struct MiniPack {
@@ -73,79 +196,286 @@ pub struct MiniPack {
 	yaw: f32,
 	members: Vec<PackMember>,
 	pub owner: Option<u8>,
+	pub kind: PackKind,
 	is_moving: bool,
 
-	moving_drop_count: u8,
+	home: (f32, f32),
+	height: f32,
+	wander_target: Option<(f32, f32)>,
+	wander_wait: f32,
+
+	path: Vec<(f32, f32)>,
+	path_goal: Option<(f32, f32)>,
+	repath_time: f32,
+
+	moving_drop_time: f32,
 
 	anim_time_count: f32,
 
-	trailing_player_distance: f32,
+	/// Position in the owner's chain, 0 follows the player directly
+	chain_index: usize,
+
+	/// Player trying to steal the pack, and progress between 0 and 1
+	pub steal: Option<(u8, f32)>,
+	protection_time: f32,
+	pub protected: bool,
+
+	/// Player who dropped the pack and can't pick it up yet, with seconds remaining
+	pickup_cooldown: Option<(u8, f32)>,
+
+	/// Seconds left running from the owner after an enemy howl
+	howl_time: f32,
 
 	interpolation: InterpolationHelper<PosYawValue>
 }
 
+pub enum PackEvent {
+	Captured { pid: u8, kind: PackKind },
+	Stolen { pid: u8, kind: PackKind }
+}
+
 #[derive(Serialize, Deserialize)]
 pub struct MiniPackUpdate {
 	position: (f32, f32),
+	/// Ground height, so clients can place packs that spawn during the match
+	height: f32,
 	yaw: f32,
 	owner: Option<u8>,
-	is_moving: bool
+	kind: PackKind,
+	is_moving: bool,
+	/// Stealing player and progress between 0 and 1
+	steal: Option<(u8, f32)>,
+	protected: bool
 }
 
 impl MiniPacks {
 	pub fn new() -> Self {
 		Self {
 			packs: Vec::new(),
-			net_update_time_count: 0.
+			net_update_time_count: 0.,
+			height: 0.,
+			wave_cooldown: 0.
+		}
+	}
+
+	fn spawn_points(map: &GameMap) -> Vec<[f32; 3]> {
+		let mut spawn_keys: Vec<&String> = map.misc_objs.keys().filter(|k| k.starts_with(SPAWN_PREFIX)).collect();
+		spawn_keys.sort();
+		spawn_keys.into_iter().map(|k| map.misc_objs[k]).collect()
+	}
+
+	/// Wolves held by each player, counting alpha packs as more than one pack
+	pub fn wolf_counts(&self) -> HashMap<u8, usize> {
+		let mut counts = HashMap::new();
+		for pack in &self.packs {
+			if let Some(owner) = pack.owner {
+				*counts.entry(owner).or_insert(0) += pack.wolves();
+			}
+		}
+		counts
+	}
+
+	/// Rolls a variant for every pack. Only called on the server, clients receive them in pack updates.
+	pub fn choose_kinds(&mut self, rules: &VariantRules) {
+		for pack in &mut self.packs {
+			pack.kind = PackKind::random(rules);
 		}
 	}
 
 	pub fn spawn(&mut self, map: &GameMap) {
+		self.height = Self::spawn_height(map);
+		self.wave_cooldown = 0.;
+		// pack updates are matched by index, so every client needs the same order and layout
+		for obj in Self::spawn_points(map) {
+			let pack = MiniPack::new((obj[0], obj[2]), obj[1], self.packs.len());
+			self.packs.push(pack);
+		}
+	}
+
+	/// Spawns a wave of neutral packs once too few are left, at spawn points
+	/// away from players, or at random walkable positions between the spawn points.
+	/// Returns the number of packs spawned.
+	pub fn respawn_update(&mut self, time_delta: f32, map: &GameMap, nav: &NavGrid, player_positions: &[[f32; 3]],
+		rules: &RespawnRules, variants: &VariantRules) -> usize {
+		if !rules.enabled {
+			return 0;
+		}
+		if self.wave_cooldown > 0. {
+			self.wave_cooldown -= time_delta;
+			return 0;
+		}
+		let neutral_count = self.packs.iter().filter(|p| p.owner.is_none()).count();
+		if neutral_count >= rules.neutral_threshold || self.packs.len() >= rules.max_packs {
+			return 0;
+		}
+
+		let far_from_players = |position: (f32, f32)| player_positions.iter().all(|p| {
+			((p[0] - position.0).powi(2) + (p[2] - position.1).powi(2)).sqrt() >= rules.min_player_distance
+		});
+		let wave_size = rules.wave_size.min(rules.max_packs - self.packs.len());
 		let mut rng = rand::thread_rng();
-		let spawn_keys: Vec<String> = map.misc_objs.keys().filter(|k| k.starts_with(SPAWN_PREFIX)).cloned().collect();
-		for spawn_key in spawn_keys {
-			let obj = map.misc_objs.get(&spawn_key).unwrap();
-			let mut new_pack = MiniPack {
-				position: (obj[0], obj[2]),
-				yaw: rng.gen_range(0.0..(std::f32::consts::PI * 2.)),
-				members: Vec::new(),
-				owner: None,
-				is_moving: false,
-				moving_drop_count: 0,
-				anim_time_count: 0.,
-				interpolation: InterpolationHelper::new(),
-				trailing_player_distance: STARTING_FOLLOW_DISTANCE
+
+		let mut points: Vec<[f32; 3]> = Self::spawn_points(map).into_iter().filter(|obj| {
+			let position = (obj[0], obj[2]);
+			far_from_players(position) && !self.packs.iter().any(|pack| {
+				pack.owner.is_none() && ((pack.position.0 - position.0).powi(2) + (pack.position.1 - position.1).powi(2)).sqrt() < RESPAWN_PACK_SPACING
+			})
+		}).collect();
+		let mut positions = Vec::with_capacity(wave_size);
+		while positions.len() < wave_size && !points.is_empty() {
+			let obj = points.swap_remove(rng.gen_range(0..points.len()));
+			positions.push(((obj[0], obj[2]), obj[1]));
+		}
+
+		let all_points = Self::spawn_points(map);
+		if !all_points.is_empty() {
+			let min = (all_points.iter().map(|p| p[0]).fold(f32::MAX, f32::min), all_points.iter().map(|p| p[2]).fold(f32::MAX, f32::min));
+			let max = (all_points.iter().map(|p| p[0]).fold(f32::MIN, f32::max), all_points.iter().map(|p| p[2]).fold(f32::MIN, f32::max));
+			let mut attempts = 0;
+			while positions.len() < wave_size && attempts < RESPAWN_POSITION_ATTEMPTS * wave_size && min.0 < max.0 && min.1 < max.1 {
+				attempts += 1;
+				let position = (rng.gen_range(min.0..max.0), rng.gen_range(min.1..max.1));
+				if nav.is_walkable(position) && far_from_players(position) {
+					positions.push((position, self.height));
+				}
+			}
+		}
+
+		for (position, height) in &positions {
+			let mut pack = MiniPack::new(*position, *height, self.packs.len());
+			pack.kind = PackKind::random(variants);
+			self.packs.push(pack);
+		}
+		self.wave_cooldown = rules.wave_interval_seconds;
+		if !positions.is_empty() {
+			info!("spawned a wave of {} neutral packs ({} were left)", positions.len(), neutral_count);
+		}
+		positions.len()
+	}
+
+	/// Average height of the pack spawn points, used to sample the nav grid
+	pub fn spawn_height(map: &GameMap) -> f32 {
+		let heights: Vec<f32> = map.misc_objs.iter().filter(|(k, _)| k.starts_with(SPAWN_PREFIX)).map(|(_, v)| v[1]).collect();
+		if heights.is_empty() {
+			0.
+		} else {
+			heights.iter().sum::<f32>() / heights.len() as f32
+		}
+	}
+
+	/// Releases the pack at the end of a player's chain, returning true if the player had one.
+	pub fn release_pack(&mut self, pid: u8, scatter: bool, nav: &NavGrid) -> bool {
+		let pack = match self.last_pack(pid) {
+			Some(pack) => pack,
+			None => return false
+		};
+		let position = if scatter { scatter_position(pack.position, nav) } else { pack.position };
+		pack.make_neutral(position);
+		true
+	}
+
+	/// Releases the pack at the end of a player's chain at the given position,
+	/// so the player can't pick it straight back up. The pack moves there at once rather than
+	/// walking back along the chain. Clients ease the centre over with the usual pack update
+	/// interpolation and the wolves flock after it, unless it was far enough back that they snap over.
+	pub fn drop_pack(&mut self, pid: u8, position: (f32, f32), cooldown_seconds: f32) -> bool {
+		let pack = match self.last_pack(pid) {
+			Some(pack) => pack,
+			None => return false
+		};
+		pack.make_neutral(position);
+		pack.pickup_cooldown = Some((pid, cooldown_seconds));
+		true
+	}
+
+	/// Scares enemy packs around a howling player, returning how many were affected
+	pub fn howl(&mut self, pid: u8, position: (f32, f32), rules: &HowlRules, teams: &Teams) -> usize {
+		let mut count = 0;
+		for pack in &mut self.packs {
+			let owner = match pack.owner {
+				Some(owner) if !teams.are_teammates(owner, pid) => owner,
+				_ => continue
 			};
-			for _ in 0..PACK_SIZE {
-				let mut member = PackMember {
-					standing_yaw: rng.gen_range(0.0..YAW_VARIANCE),
-					..Default::default()
-				};
-				loop {
-					member.pos_offset = (
-						rng.gen_range(-POSITION_VARIANCE..POSITION_VARIANCE),
-						rng.gen_range(-POSITION_VARIANCE..POSITION_VARIANCE)
-					);
+			let distance = ((pack.position.0 - position.0).powi(2) + (pack.position.1 - position.1).powi(2)).sqrt();
+			if distance > rules.radius { continue; }
+			pack.howl_time = rules.duration_seconds;
+			pack.path.clear();
+			pack.path_goal = None;
+			info!("player {} scared a pack of player {}", pid, owner);
+			count += 1;
+		}
+		count
+	}
 
-					member.draw_info.position[1] = obj[1];
+	/// Wolves in the pack the player would drop next
+	pub fn last_pack_wolves(&self, pid: u8) -> usize {
+		self.packs.iter()
+			.filter(|p| p.owner == Some(pid))
+			.max_by_key(|p| p.chain_index)
+			.map(|p| p.wolves())
+			.unwrap_or(0)
+	}
 
-					if new_pack.members.iter().filter(|other| {
-						let distance = vector_length(
-							&add_vector(&[member.pos_offset.0, 0., member.pos_offset.1], &[other.pos_offset.0, 0., member.pos_offset.1], -1.)
-						);
-						distance < MIN_MEMBER_DISTANCE
-					}).count() == 0 {
-						break;
-					}
+	fn last_pack(&mut self, pid: u8) -> Option<&mut MiniPack> {
+		self.packs.iter_mut()
+			.filter(|p| p.owner == Some(pid))
+			.max_by_key(|p| p.chain_index)
+	}
+
+	/// Orders the packs of each player into a chain. Existing order is kept,
+	/// packs that were gained are appended and gaps from lost packs are closed.
+	fn update_chains(&mut self) -> HashMap<u8, Vec<usize>> {
+		let mut chains: HashMap<u8, Vec<usize>> = HashMap::new();
+		for (i, pack) in self.packs.iter().enumerate() {
+			if let Some(owner) = pack.owner {
+				chains.entry(owner).or_default().push(i);
+			}
+		}
+		for indices in chains.values_mut() {
+			let packs = &self.packs;
+			indices.sort_by_key(|i| (packs[*i].chain_index, *i));
+			for (chain_index, i) in indices.iter().enumerate() {
+				self.packs[*i].chain_index = chain_index;
+			}
+		}
+		chains
+	}
+
+	/// Moves owned packs in a conga line: the first pack follows its owner and
+	/// every other pack follows the one in front of it. Packs scared by a howl run from the owner instead.
+	pub fn follow_update(&mut self, time_delta: f32, player_map: &HashMap<u8, Player>, rules: &MatchRules, nav: &NavGrid) {
+		for (owner, indices) in self.update_chains() {
+			let player = match player_map.get(&owner) {
+				Some(player) => player,
+				None => continue
+			};
+			let owner_position = (player.camera.position[0], player.camera.position[2]);
+			let leading_distance = rules.starting_follow_distance + rules.follow_distance_incr * (indices.len() - 1) as f32;
+			let mut leader = owner_position;
+			for i in indices {
+				let pack = &mut self.packs[i];
+				if pack.howl_time > 0. {
+					pack.run_from(owner_position, time_delta, nav);
+					leader = pack.position;
+					continue;
 				}
-				new_pack.members.push(member);
+				let distance = if pack.chain_index == 0 { leading_distance } else { rules.chain_spacing };
+				pack.follow(leader, distance, time_delta, nav);
+				leader = pack.position;
 			}
-			self.packs.push(new_pack);
 		}
 	}
 
-	pub fn client_update_msg(&mut self, msg: AppMessage) {
+	/// Applies a pack update from the server, returning the new owner and variant of every pack that changed hands
+	pub fn client_update_msg(&mut self, msg: AppMessage) -> Vec<(u8, PackKind)> {
+		let mut gained = Vec::new();
 		if let AppMessage::PackUpdate(pack_updates) = msg {
+			// packs spawned by waves during the match appear at the end of the update
+			self.packs.truncate(pack_updates.len());
+			for (index, pack_update) in pack_updates.iter().enumerate().skip(self.packs.len()) {
+				let pack = MiniPack::new(pack_update.position, pack_update.height, index);
+				self.packs.push(pack);
+			}
 			let mut packs_iter = self.packs.iter_mut();
 			for pack_update in &pack_updates {
 				if let Some(pack) = packs_iter.next() {
@@ -153,14 +483,96 @@ impl MiniPacks {
 						pos: pack_update.position,
 						yaw: pack_update.yaw
 					});
+					// the kind before the update, since speed packs turn normal once captured
+					if let Some(owner) = pack_update.owner.filter(|owner| pack.owner != Some(*owner)) {
+						gained.push((owner, pack.kind));
+					}
 					pack.owner = pack_update.owner;
+					pack.kind = pack_update.kind;
 					if !pack.is_moving && pack_update.is_moving {
 						pack.anim_time_count = 0.;
 					}
 					pack.is_moving = pack_update.is_moving;
+					pack.steal = pack_update.steal;
+					pack.protected = pack_update.protected;
 				}
 			}
 		}
+		gained
+	}
+
+	/// Advances steal progress for owned packs. A steal completes once a single side has stayed
+	/// within pickup distance for `capture_seconds`, and is frozen while several sides contest it.
+	/// Packs of shielded players can't be stolen.
+	pub fn steal_update(&mut self, time_delta: f32, players: &[(u8, [f32; 3])], player_pack_counts: &mut HashMap<u8, usize>,
+		rules: &MatchRules, teams: &Teams, mode: &dyn GameMode, shielded: &HashSet<u8>) -> Vec<PackEvent> {
+		let mut events = Vec::new();
+		let step = if rules.steal.capture_seconds > 0. { time_delta / rules.steal.capture_seconds } else { 1. };
+		let mut wolf_counts = self.wolf_counts();
+		for pack in &mut self.packs {
+			let owner_id = match pack.owner {
+				Some(owner_id) => owner_id,
+				None => {
+					pack.steal = None;
+					pack.protected = false;
+					continue;
+				}
+			};
+			pack.protected = pack.protection_time > 0. || shielded.contains(&owner_id);
+			if pack.protected {
+				pack.protection_time = (pack.protection_time - time_delta).max(0.);
+				pack.steal = None;
+				continue;
+			}
+
+			let in_range: Vec<u8> = players.iter().filter(|(_, position)| {
+				let x = position[0] - pack.position.0;
+				let z = position[2] - pack.position.1;
+				(x * x + z * z).sqrt() < rules.pickup_distance
+			}).map(|(pid, _)| *pid).collect();
+			let defended = rules.steal.owner_defends && in_range.iter().any(|pid| teams.are_teammates(*pid, owner_id));
+			// scared packs can be stolen by anyone on the other side
+			let howled = pack.howl_time > 0.;
+			let stealers: Vec<u8> = in_range.into_iter().filter(|pid| {
+				!teams.are_teammates(*pid, owner_id) && (howled || mode.can_steal(*pid, owner_id, &wolf_counts, rules, teams))
+			}).collect();
+			let contested = stealers.iter().any(|pid| !teams.are_teammates(*pid, stealers[0]));
+
+			if stealers.is_empty() || defended {
+				pack.steal = pack.steal.map(|(pid, progress)| (pid, progress - step)).filter(|(_, progress)| *progress > 0.);
+				continue;
+			}
+			if contested {
+				continue;
+			}
+
+			// keep progress if the same side is still stealing
+			let (stealer, progress) = match pack.steal {
+				Some((pid, progress)) if teams.are_teammates(pid, stealers[0]) => {
+					(if stealers.contains(&pid) { pid } else { stealers[0] }, progress)
+				},
+				_ => (stealers[0], 0.)
+			};
+			let progress = progress + step;
+			if progress < 1. {
+				pack.steal = Some((stealer, progress));
+				continue;
+			}
+
+			let own_wolves = *wolf_counts.get(&stealer).unwrap_or(&0);
+			let other_wolves = *wolf_counts.get(&owner_id).unwrap_or(&0);
+			pack.set_owner(stealer);
+			pack.protection_time = rules.steal.protection_seconds;
+			wolf_counts.insert(stealer, own_wolves + pack.wolves());
+			wolf_counts.insert(owner_id, other_wolves.saturating_sub(pack.wolves()));
+			*player_pack_counts.entry(stealer).or_insert(0) += 1;
+			let other_pack_counts = player_pack_counts.entry(owner_id).or_insert(0);
+			*other_pack_counts = other_pack_counts.saturating_sub(1);
+			info!("player {} stole a pack from player {} ({} vs {} wolves)", stealer, owner_id,
+				own_wolves, other_wolves);
+			events.push(PackEvent::Stolen { pid: stealer, kind: pack.take_kind() });
+		}
+		events
 	}
 
 	pub fn server_update_msg(&mut self, time_delta: f32) -> Option<AppMessage> {
@@ -171,9 +583,13 @@ impl MiniPacks {
 				self.packs.iter().map(|v| {
 					MiniPackUpdate {
 						position: v.position,
+						height: v.height,
 						yaw: v.yaw,
 						owner: v.owner,
-						is_moving: v.is_moving
+						kind: v.kind,
+						is_moving: v.is_moving,
+						steal: v.steal,
+						protected: v.protected
 					}
 				}).collect()
 			))
@@ -184,72 +600,341 @@ impl MiniPacks {
 }
 
 impl MiniPack {
+	/// Members are laid out from the index, so the server and clients create identical packs
+	fn new(position: (f32, f32), height: f32, index: usize) -> Self {
+		let mut rng = StdRng::seed_from_u64(index as u64);
+		let mut new_pack = MiniPack {
+			position: position,
+			yaw: rng.gen_range(0.0..(std::f32::consts::PI * 2.)),
+			members: Vec::new(),
+			owner: None,
+			kind: PackKind::Normal,
+			is_moving: false,
+			home: position,
+			height: height,
+			wander_target: None,
+			wander_wait: rng.gen_range(WANDER_MIN_WAIT..WANDER_MAX_WAIT),
+			path: Vec::new(),
+			path_goal: None,
+			repath_time: 0.,
+			moving_drop_time: 0.,
+			anim_time_count: 0.,
+			interpolation: InterpolationHelper::new(),
+			chain_index: NEW_CHAIN_INDEX,
+			steal: None,
+			protection_time: 0.,
+			protected: false,
+			pickup_cooldown: None,
+			howl_time: 0.
+		};
+		for _ in 0..PACK_SIZE {
+			let mut member = PackMember {
+				yaw: new_pack.yaw + rng.gen_range(0.0..YAW_VARIANCE),
+				anim_phase: rng.gen_range(0.0..ANIM_PHASE_VARIANCE),
+				..Default::default()
+			};
+			loop {
+				member.pos_offset = (
+					rng.gen_range(-POSITION_VARIANCE..POSITION_VARIANCE),
+					rng.gen_range(-POSITION_VARIANCE..POSITION_VARIANCE)
+				);
 
-	fn update_follow_distance(&mut self, pack_counts: usize) {
-		self.trailing_player_distance = STARTING_FOLLOW_DISTANCE + (pack_counts as f32 * FOLLOW_DISTANCE_INCR);
+				member.draw_info.position[1] = height;
+
+				if new_pack.members.iter().filter(|other| {
+					let distance = vector_length(
+						&add_vector(&[member.pos_offset.0, 0., member.pos_offset.1], &[other.pos_offset.0, 0., other.pos_offset.1], -1.)
+					);
+					distance < MIN_MEMBER_DISTANCE
+				}).count() == 0 {
+					break;
+				}
+			}
+			member.position = (position.0 + member.pos_offset.0, position.1 + member.pos_offset.1);
+			new_pack.members.push(member);
+		}
+		new_pack
 	}
 
-	pub fn player_server_update(&mut self, pid: u8, player: &Player, player_pack_counts: &mut HashMap<u8, usize>) {
-		let diff = add_vector(&player.camera.position, &[self.position.0, 0., self.position.1], -1.);
-		let distance = vector_length(&diff);
+	pub fn wolves(&self) -> usize {
+		match self.kind {
+			PackKind::Alpha => ALPHA_PACK_WOLVES,
+			_ => PACK_SIZE
+		}
+	}
 
-		let own_pack_counts = *player_pack_counts.entry(pid).or_insert(0);
+	fn make_neutral(&mut self, position: (f32, f32)) {
+		self.owner = None;
+		self.position = position;
+		// released packs roam around where they were dropped
+		self.home = position;
+		self.wander_target = None;
+		self.path.clear();
+		self.path_goal = None;
+	}
 
+	fn set_owner(&mut self, pid: u8) {
+		self.owner = Some(pid);
+		self.chain_index = NEW_CHAIN_INDEX;
+		self.path.clear();
+		self.path_goal = None;
+	}
+
+	/// Turns towards a yaw at a limited rate, taking the shortest way around
+	fn turn_towards(&mut self, target_yaw: f32, time_delta: f32) {
+		let two_pi = std::f32::consts::PI * 2.;
+		let mut diff = (target_yaw - self.yaw) % two_pi;
+		if diff > std::f32::consts::PI {
+			diff -= two_pi;
+		} else if diff < -std::f32::consts::PI {
+			diff += two_pi;
+		}
+		let max_turn = TURN_RATE * time_delta;
+		let yaw = self.yaw + diff.max(-max_turn).min(max_turn);
+		self.yaw = direction_yaw(yaw.cos(), yaw.sin());
+	}
+
+	/// Roams around the home position, and runs from players who get close.
+	fn neutral_update(&mut self, time_delta: f32, nav: &NavGrid, player_positions: &[[f32; 3]]) {
+		let mut rng = rand::thread_rng();
+
+		let nearest_player = player_positions.iter()
+			.map(|p| (p[0] - self.position.0, p[2] - self.position.1))
+			.map(|(x, z)| (x, z, (x * x + z * z).sqrt()))
+			.filter(|(_, _, distance)| *distance < FLEE_DISTANCE && *distance > 0.)
+			.min_by(|a, b| a.2.partial_cmp(&b.2).unwrap());
+
+		let (direction, speed, leash_radius) = match (nearest_player, self.wander_target) {
+			(Some((x, z, distance)), _) => {
+				self.wander_target = None;
+				((-x / distance, -z / distance), FLEE_SPEED, FLEE_LEASH_RADIUS)
+			},
+			(None, Some(target)) => {
+				let x = target.0 - self.position.0;
+				let z = target.1 - self.position.1;
+				let distance = (x * x + z * z).sqrt();
+				if distance < WANDER_ARRIVE_DISTANCE {
+					self.wander_target = None;
+					self.wander_wait = rng.gen_range(WANDER_MIN_WAIT..WANDER_MAX_WAIT);
+					return;
+				}
+				((x / distance, z / distance), WANDER_SPEED, WANDER_LEASH_RADIUS)
+			},
+			(None, None) => {
+				self.wander_wait -= time_delta;
+				if self.wander_wait <= 0. {
+					let angle = rng.gen_range(0.0..(std::f32::consts::PI * 2.));
+					let distance = rng.gen_range(0.0..WANDER_LEASH_RADIUS);
+					self.wander_target = Some((self.home.0 + angle.cos() * distance, self.home.1 + angle.sin() * distance));
+				}
+				return;
+			}
+		};
+
+		let step = speed * time_delta;
+		let next = (self.position.0 + direction.0 * step, self.position.1 + direction.1 * step);
+		let home_distance = ((next.0 - self.home.0).powi(2) + (next.1 - self.home.1).powi(2)).sqrt();
+		if home_distance > leash_radius || !nav.is_walkable(next) {
+			// give up on this direction and pick another one after a short rest
+			self.wander_target = None;
+			self.wander_wait = rng.gen_range(0.0..WANDER_MIN_WAIT);
+			return;
+		}
+
+		self.turn_towards(direction_yaw(direction.0, direction.1), time_delta);
+		self.position = next;
+		self.is_moving = true;
+		self.moving_drop_time = 0.;
+	}
+
+	pub fn server_update(&mut self, time_delta: f32, nav: &NavGrid, player_positions: &[[f32; 3]]) {
 		if self.is_moving {
-			self.moving_drop_count += 1;
-			if self.moving_drop_count > MAX_MOVING_DROP_COUNT {
+			self.moving_drop_time += time_delta;
+			if self.moving_drop_time > MOVING_DROP_SECONDS {
 				self.is_moving = false;
 			}
 		}
+		if self.owner.is_none() {
+			self.neutral_update(time_delta, nav, player_positions);
+		}
+		self.pickup_cooldown = self.pickup_cooldown.map(|(pid, time)| (pid, time - time_delta)).filter(|(_, time)| *time > 0.);
+		self.howl_time = (self.howl_time - time_delta).max(0.);
+	}
 
-		match self.owner {
-			Some(owner_id) => {
-				let other_pack_counts = *player_pack_counts.entry(owner_id).or_insert(0);
-				if owner_id == pid {
-					if distance > self.trailing_player_distance {
-						let mve = mult_vector(&diff, (distance - self.trailing_player_distance + 0.05) / distance);
-						self.yaw = (mve[2] / mve[0]).atan();
-						if mve[0] < 0. {
-							self.yaw -= std::f32::consts::PI;
-						}
-						self.position.0 += mve[0];
-						self.position.1 += mve[2];
-						self.is_moving = true;
-						self.moving_drop_count = 0;
-					}
-				} else {
-					if distance < PICKUP_DISTANCE && own_pack_counts > other_pack_counts {
-						self.owner = Some(pid);
-						player_pack_counts.insert(pid, own_pack_counts + 1);
-						player_pack_counts.insert(owner_id, own_pack_counts - 1);
-						self.update_follow_distance(own_pack_counts);
-					}
+	/// Moves straight away from a position, stopping at walls
+	fn run_from(&mut self, from: (f32, f32), time_delta: f32, nav: &NavGrid) {
+		let x = self.position.0 - from.0;
+		let z = self.position.1 - from.1;
+		let distance = (x * x + z * z).sqrt();
+		if distance == 0. {
+			return;
+		}
+		let step = HOWL_PUSH_SPEED * time_delta;
+		let next = (self.position.0 + x / distance * step, self.position.1 + z / distance * step);
+		if !nav.is_walkable(next) {
+			return;
+		}
+		self.yaw = direction_yaw(x, z);
+		self.position = next;
+		self.is_moving = true;
+		self.moving_drop_time = 0.;
+	}
+
+	/// Moves up to `trailing_distance` away from the target, pathfinding around walls
+	/// when there is no clear line towards it. If no path is found, the previous one is
+	/// kept, or the pack holds its position until the next search.
+	fn follow(&mut self, target: (f32, f32), trailing_distance: f32, time_delta: f32, nav: &NavGrid) {
+		self.repath_time = (self.repath_time - time_delta).max(0.);
+		let diff = (target.0 - self.position.0, target.1 - self.position.1);
+		let distance = (diff.0 * diff.0 + diff.1 * diff.1).sqrt();
+		if distance <= trailing_distance {
+			return;
+		}
+
+		let new_position = if nav.line_clear(self.position, target) {
+			self.path.clear();
+			self.path_goal = None;
+			let progress = (distance - trailing_distance + 0.05) / distance;
+			(self.position.0 + diff.0 * progress, self.position.1 + diff.1 * progress)
+		} else {
+			let repath = self.path.is_empty() || self.path_goal.map(|goal| {
+				((goal.0 - target.0).powi(2) + (goal.1 - target.1).powi(2)).sqrt() > REPATH_DISTANCE
+			}).unwrap_or(true);
+			if repath && self.repath_time <= 0. {
+				self.repath_time = REPATH_INTERVAL;
+				if let Some(path) = nav.find_path(self.position, target) {
+					self.path = path;
+					self.path_goal = Some(target);
 				}
-			},
-			None => {
-				if distance < PICKUP_DISTANCE {
-					self.owner = Some(pid);
-					player_pack_counts.insert(pid, own_pack_counts + 1);
-					self.update_follow_distance(own_pack_counts);
+			} else if !repath {
+				if let Some(last) = self.path.last_mut() {
+					*last = target;
 				}
 			}
+			if self.path.is_empty() {
+				return;
+			}
+			advance_along(&mut self.path, self.position, trailing_distance)
 		};
+
+		let mve = (new_position.0 - self.position.0, new_position.1 - self.position.1);
+		if mve.0 == 0. && mve.1 == 0. {
+			return;
+		}
+		self.yaw = direction_yaw(mve.0, mve.1);
+		self.position = new_position;
+		self.is_moving = true;
+		self.moving_drop_time = 0.;
+	}
+
+	/// Kind for a capture or steal event. Speed packs only boost their first owner.
+	fn take_kind(&mut self) -> PackKind {
+		let kind = self.kind;
+		if kind == PackKind::Speed {
+			self.kind = PackKind::Normal;
+		}
+		kind
 	}
 
-	pub fn client_update(&mut self, quadoctree: &QuadOctreeNode, time_delta: f32) {
+	/// Captures the pack if it is neutral and the player is close enough.
+	/// Steals from other players are handled by `MiniPacks::steal_update`.
+	pub fn player_server_update(&mut self, pid: u8, player: &Player, player_pack_counts: &mut HashMap<u8, usize>,
+		pickup_distance: f32, mode: &dyn GameMode, nav: &NavGrid) -> Option<PackEvent> {
+		let own_pack_counts = *player_pack_counts.entry(pid).or_insert(0);
+		if self.owner.is_some() || self.pickup_cooldown.map(|(dropper, _)| dropper == pid).unwrap_or(false) {
+			return None;
+		}
+
+		let diff = add_vector(&player.camera.position, &[self.position.0, 0., self.position.1], -1.);
+		if vector_length(&diff) < pickup_distance && mode.can_pickup(pid) {
+			if self.kind == PackKind::Decoy {
+				let position = scatter_position(self.position, nav);
+				self.make_neutral(position);
+				self.kind = PackKind::Normal;
+				info!("player {} scattered a decoy pack", pid);
+				return None;
+			}
+			self.set_owner(pid);
+			player_pack_counts.insert(pid, own_pack_counts + 1);
+			info!("player {} captured a neutral pack", pid);
+			return Some(PackEvent::Captured { pid: pid, kind: self.take_kind() });
+		}
+		None
+	}
+
+	/// Boids simulation of the members: cohesion, separation, alignment and
+	/// following the pack centre, which acts as the leader.
+	fn flock_update(&mut self, time_delta: f32) {
+		let time_delta = time_delta.min(0.1);
+		let count = self.members.len() as f32;
+		if count == 0. { return; }
+		let snapshot: Vec<((f32, f32), (f32, f32))> = self.members.iter().map(|m| (m.position, m.velocity)).collect();
+		let average_position = (snapshot.iter().map(|(p, _)| p.0).sum::<f32>() / count,
+			snapshot.iter().map(|(p, _)| p.1).sum::<f32>() / count);
+		let average_velocity = (snapshot.iter().map(|(_, v)| v.0).sum::<f32>() / count,
+			snapshot.iter().map(|(_, v)| v.1).sum::<f32>() / count);
+
+		for (i, member) in self.members.iter_mut().enumerate() {
+			let target = (self.position.0 + member.pos_offset.0, self.position.1 + member.pos_offset.1);
+			let to_target = (target.0 - member.position.0, target.1 - member.position.1);
+			if (to_target.0 * to_target.0 + to_target.1 * to_target.1).sqrt() > FLOCK_SNAP_DISTANCE {
+				// spawned, scattered or far out of sync, don't run across the map
+				member.position = target;
+				member.velocity = (0., 0.);
+				continue;
+			}
+
+			let mut accel = (
+				to_target.0 * FLOCK_FOLLOW + (average_position.0 - member.position.0) * FLOCK_COHESION
+					+ (average_velocity.0 - member.velocity.0) * FLOCK_ALIGNMENT,
+				to_target.1 * FLOCK_FOLLOW + (average_position.1 - member.position.1) * FLOCK_COHESION
+					+ (average_velocity.1 - member.velocity.1) * FLOCK_ALIGNMENT
+			);
+			for (j, (other_position, _)) in snapshot.iter().enumerate() {
+				if i == j { continue; }
+				let away = (member.position.0 - other_position.0, member.position.1 - other_position.1);
+				let distance_sq = away.0 * away.0 + away.1 * away.1;
+				if distance_sq > 0. && distance_sq < FLOCK_SEPARATION_DISTANCE * FLOCK_SEPARATION_DISTANCE {
+					accel.0 += away.0 / distance_sq * FLOCK_SEPARATION;
+					accel.1 += away.1 / distance_sq * FLOCK_SEPARATION;
+				}
+			}
+
+			let damping = (1. - FLOCK_DAMPING * time_delta).max(0.);
+			member.velocity = ((member.velocity.0 + accel.0 * time_delta) * damping,
+				(member.velocity.1 + accel.1 * time_delta) * damping);
+			let speed = (member.velocity.0 * member.velocity.0 + member.velocity.1 * member.velocity.1).sqrt();
+			if speed > FLOCK_MAX_SPEED {
+				member.velocity = (member.velocity.0 / speed * FLOCK_MAX_SPEED, member.velocity.1 / speed * FLOCK_MAX_SPEED);
+			}
+			member.position.0 += member.velocity.0 * time_delta;
+			member.position.1 += member.velocity.1 * time_delta;
+			if member.is_moving() {
+				member.yaw = direction_yaw(member.velocity.0, member.velocity.1);
+			}
+		}
+	}
+
+	pub fn client_update(&mut self, quadoctree: &QuadOctreeNode, time_delta: f32, teams: &Teams) {
 		if let Some(pos_yaw) = self.interpolation.value(time_delta) {
 			self.position = pos_yaw.pos;
 			self.yaw = pos_yaw.yaw;
 		}
 
+		self.flock_update(time_delta);
+
 		let owner = self.owner.as_ref();
+		let tint = self.kind.tint();
+		let scale = self.kind.scale();
 
 		for member in &mut self.members {
-			member.draw_info.position[0] = member.pos_offset.0 + self.position.0;
-			member.draw_info.position[2] = member.pos_offset.1 + self.position.1;
-			if let Some(pid) = owner {
-				member.draw_info.color = *player_color(*pid);
-			}
+			member.draw_info.position[0] = member.position.0;
+			member.draw_info.position[2] = member.position.1;
+			member.draw_info.color = match owner {
+				Some(pid) => teams.color(*pid),
+				None => tint
+			};
+			member.draw_info.scale = [scale, scale, scale];
 
 			'f: for _ in 0..15 {
 				let collide_obj = generate_cube_collideobj(&[0., 0.5, 0.], &member.draw_info.position, &[0.5, 0.5, 0.5], 0.);
@@ -266,7 +951,7 @@ impl MiniPack {
 				member.draw_info.position[1] -= COLLIDE_CHECK_DECR;
 			}
 
-			member.draw_info.rotation[1] = self.yaw + if owner.is_some() { 0. } else { member.standing_yaw };
+			member.draw_info.rotation[1] = member.yaw;
 			member.draw_info.generate_matrix();
 		}
 
@@ -275,12 +960,12 @@ impl MiniPack {
 
 	pub fn draw(&self, target: &mut Frame, env_info: &EnvDrawInfo, program: &Program, wolf_anim: &ObjAnimation, wolf_standing: &BTreeMap<String, ObjDef>) {
 		for member in &self.members {
-			if !self.is_moving {
+			if !member.is_moving() {
 				for obj in wolf_standing.values() {
 					basic_render(target, env_info, &member.draw_info, obj, program, None);
 				}
 			} else {
-				for o in wolf_anim.get_keyframe(self.anim_time_count).values() {
+				for o in wolf_anim.get_keyframe(self.anim_time_count + member.anim_phase).values() {
 					basic_render(target, env_info, &member.draw_info, &o, program, None);
 				}
 			}
@@ -0,0 +1,49 @@
+use std::collections::HashMap;
+use crate::mode::{GameMode, GameModeKind, ModeContext, GameEnd};
+
+/// Players who lose every pack are eliminated, and the last pack owner standing wins.
+pub struct FreeForAll;
+
+impl FreeForAll {
+	fn last_player_standing(ctx: &ModeContext) -> Option<u8> {
+		let owners: Vec<u8> = ctx.player_pack_counts.iter().filter(|(_, count)| **count > 0).map(|(pid, _)| *pid).collect();
+		if owners.is_empty() || ctx.player_map.len() < 2 {
+			return None;
+		}
+		if !owners.iter().all(|pid| ctx.teams.are_teammates(*pid, owners[0])) {
+			return None;
+		}
+		let others_eliminated = ctx.player_map.keys().all(|pid| ctx.teams.are_teammates(*pid, owners[0]) || ctx.eliminated.contains(pid));
+		// without neutral packs, players with no packs can never capture one, unless stealing ignores pack size
+		let neutral_left = ctx.packs.packs.iter().any(|p| p.owner.is_none());
+		if others_eliminated || (!neutral_left && ctx.rules.steal_requires_bigger_pack) {
+			owners.into_iter().max_by_key(|pid| *ctx.player_pack_counts.get(pid).unwrap_or(&0))
+		} else {
+			None
+		}
+	}
+}
+
+impl GameMode for FreeForAll {
+	fn kind(&self) -> GameModeKind {
+		GameModeKind::FreeForAll
+	}
+
+	fn uses_elimination(&self) -> bool {
+		true
+	}
+
+	fn end_condition(&self, ctx: &ModeContext, elapsed_secs: usize) -> Option<GameEnd> {
+		if let Some(winner) = Self::last_player_standing(ctx) {
+			return Some(GameEnd::won_by(winner));
+		}
+		if elapsed_secs >= ctx.rules.game_seconds {
+			return Some(GameEnd::from_scores(&self.scores(&ctx.packs.wolf_counts()), ctx.teams));
+		}
+		None
+	}
+
+	fn scores(&self, player_wolf_counts: &HashMap<u8, usize>) -> HashMap<u8, u32> {
+		player_wolf_counts.iter().map(|(pid, count)| (*pid, *count as u32)).collect()
+	}
+}
@@ -0,0 +1,128 @@
+use cubik::map::GameMap;
+use serde::{Serialize, Deserialize};
+use std::collections::HashMap;
+use crate::mode::{GameMode, GameModeKind, ModeContext};
+use crate::zone::ZoneState;
+use log::info;
+
+const HILL_PREFIX: &str = "misc_hill_";
+
+#[derive(Serialize, Deserialize, Clone)]
+#[serde(default)]
+pub struct HillRules {
+	pub radius: f32,
+	/// Seconds before the next hill becomes active, if the map has more than one
+	pub rotate_seconds: usize
+}
+
+impl Default for HillRules {
+	fn default() -> Self {
+		Self {
+			radius: 6.,
+			rotate_seconds: 30
+		}
+	}
+}
+
+#[derive(Serialize, Deserialize, Clone, Copy, PartialEq)]
+pub struct Hill {
+	pub center: (f32, f32),
+	pub radius: f32,
+	/// None if the hill never moves
+	pub seconds_to_rotate: Option<u16>
+}
+
+impl Hill {
+	pub fn contains(&self, position: (f32, f32)) -> bool {
+		let x = position.0 - self.center.0;
+		let z = position.1 - self.center.1;
+		(x * x + z * z).sqrt() <= self.radius
+	}
+}
+
+/// Players earn a point every second for each wolf they hold inside the active hill.
+pub struct KingOfTheHill {
+	hill_centers: Vec<(f32, f32)>,
+	active_hill: Option<Hill>,
+	points: HashMap<u8, u32>
+}
+
+impl KingOfTheHill {
+	pub fn new() -> Self {
+		Self {
+			hill_centers: Vec::new(),
+			active_hill: None,
+			points: HashMap::new()
+		}
+	}
+
+	/// Hills in name order, or the zone center if the map doesn't define any
+	fn find_hills(map: &GameMap) -> Vec<(f32, f32)> {
+		let mut hills: Vec<(&String, &[f32; 3])> = map.misc_objs.iter()
+			.filter(|(k, _)| k.starts_with(HILL_PREFIX))
+			.collect();
+		hills.sort_by(|a, b| a.0.cmp(b.0));
+		let centers: Vec<(f32, f32)> = hills.into_iter().map(|(_, v)| (v[0], v[2])).collect();
+		if centers.is_empty() {
+			vec![ZoneState::for_map(map).center()]
+		} else {
+			centers
+		}
+	}
+
+	/// None until `start` has found the hills, e.g. when overtime is forced before a match
+	fn hill_at(&self, elapsed_secs: usize, ctx: &ModeContext) -> Option<Hill> {
+		let rules = &ctx.rules.hill;
+		let rotates = self.hill_centers.len() > 1 && rules.rotate_seconds > 0;
+		let (index, seconds_to_rotate) = if rotates {
+			((elapsed_secs / rules.rotate_seconds) % self.hill_centers.len(),
+				Some((rules.rotate_seconds - elapsed_secs % rules.rotate_seconds) as u16))
+		} else {
+			(0, None)
+		};
+		Some(Hill {
+			center: *self.hill_centers.get(index)?,
+			radius: rules.radius,
+			seconds_to_rotate: seconds_to_rotate
+		})
+	}
+}
+
+impl GameMode for KingOfTheHill {
+	fn kind(&self) -> GameModeKind {
+		GameModeKind::KingOfTheHill
+	}
+
+	fn start(&mut self, ctx: &ModeContext) {
+		self.hill_centers = Self::find_hills(ctx.map);
+		self.active_hill = self.hill_at(0, ctx);
+	}
+
+	fn tick(&mut self, ctx: &ModeContext, elapsed_secs: usize) {
+		let hill = match self.hill_at(elapsed_secs, ctx) {
+			Some(hill) => hill,
+			None => return
+		};
+		if self.active_hill.map(|h| h.center != hill.center).unwrap_or(false) {
+			info!("hill moved to ({:.2}, {:.2})", hill.center.0, hill.center.1);
+		}
+		self.active_hill = Some(hill);
+
+		for pack in &ctx.packs.packs {
+			let owner = match pack.owner {
+				Some(owner) => owner,
+				None => continue
+			};
+			if ctx.eliminated.contains(&owner) || !hill.contains(pack.position) { continue; }
+			*self.points.entry(owner).or_insert(0) += pack.wolves() as u32;
+		}
+	}
+
+	fn hill(&self) -> Option<Hill> {
+		self.active_hill
+	}
+
+	fn scores(&self, _player_wolf_counts: &HashMap<u8, usize>) -> HashMap<u8, u32> {
+		self.points.clone()
+	}
+}
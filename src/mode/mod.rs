@@ -0,0 +1,168 @@
+mod free_for_all;
+mod timed_score;
+mod king_of_the_hill;
+
+use cubik::map::GameMap;
+use cubik::player::Player;
+use serde::{Serialize, Deserialize};
+use std::collections::{HashMap, HashSet};
+use crate::minipack::MiniPacks;
+use crate::rules::MatchRules;
+use crate::team::Teams;
+
+pub use self::free_for_all::FreeForAll;
+pub use self::timed_score::TimedScore;
+pub use self::king_of_the_hill::{KingOfTheHill, Hill, HillRules};
+
+#[derive(Serialize, Deserialize, Clone, Copy, PartialEq, Debug)]
+#[serde(rename_all = "snake_case")]
+pub enum GameModeKind {
+	FreeForAll,
+	TimedScore,
+	KingOfTheHill
+}
+
+impl Default for GameModeKind {
+	fn default() -> Self {
+		GameModeKind::FreeForAll
+	}
+}
+
+impl GameModeKind {
+	pub fn create(&self) -> Box<dyn GameMode> {
+		match self {
+			GameModeKind::FreeForAll => Box::new(FreeForAll),
+			GameModeKind::TimedScore => Box::new(TimedScore::new()),
+			GameModeKind::KingOfTheHill => Box::new(KingOfTheHill::new())
+		}
+	}
+
+	pub fn name(&self) -> &'static str {
+		match self {
+			GameModeKind::FreeForAll => "Free for All",
+			GameModeKind::TimedScore => "Timed Score",
+			GameModeKind::KingOfTheHill => "King of the Hill"
+		}
+	}
+
+	/// Whether players are ranked by points instead of wolf counts
+	pub fn uses_points(&self) -> bool {
+		match self {
+			GameModeKind::FreeForAll => false,
+			_ => true
+		}
+	}
+}
+
+pub struct ModeContext<'a> {
+	pub map: &'a GameMap,
+	pub player_map: &'a HashMap<u8, Player>,
+	pub packs: &'a MiniPacks,
+	pub player_pack_counts: &'a HashMap<u8, usize>,
+	pub rules: &'a MatchRules,
+	pub teams: &'a Teams,
+	pub eliminated: &'a HashSet<u8>
+}
+
+pub struct GameEnd {
+	pub winner: Option<u8>,
+	/// Players tied for the lead when there is no winner
+	pub tied: Vec<u8>
+}
+
+impl GameEnd {
+	pub fn won_by(winner: u8) -> Self {
+		Self { winner: Some(winner), tied: Vec::new() }
+	}
+
+	/// Ends on the current scores, leaving tied leaders without a winner
+	pub fn from_scores(scores: &HashMap<u8, u32>, teams: &Teams) -> Self {
+		match leading_player(scores, teams) {
+			Some(winner) => Self::won_by(winner),
+			None => Self { winner: None, tied: tied_leaders(scores, teams) }
+		}
+	}
+}
+
+/// Rule set of a match. Hooks are only called on the server.
+pub trait GameMode {
+	fn kind(&self) -> GameModeKind;
+
+	fn start(&mut self, _ctx: &ModeContext) {}
+
+	/// Called once a second while the game is in progress.
+	fn tick(&mut self, _ctx: &ModeContext, _elapsed_secs: usize) {}
+
+	fn can_pickup(&self, _pid: u8) -> bool {
+		true
+	}
+
+	/// Pack sizes are compared in wolves, so alpha packs count for more.
+	fn can_steal(&self, pid: u8, owner_id: u8, player_wolf_counts: &HashMap<u8, usize>,
+		rules: &MatchRules, teams: &Teams) -> bool {
+		!teams.are_teammates(pid, owner_id) && (!rules.steal_requires_bigger_pack ||
+			teams.side_total(pid, player_wolf_counts) > teams.side_total(owner_id, player_wolf_counts))
+	}
+
+	/// Whether players who lose all of their packs are eliminated.
+	fn uses_elimination(&self) -> bool {
+		false
+	}
+
+	fn end_condition(&self, ctx: &ModeContext, elapsed_secs: usize) -> Option<GameEnd> {
+		if elapsed_secs >= ctx.rules.game_seconds {
+			Some(GameEnd::from_scores(&self.scores(&ctx.packs.wolf_counts()), ctx.teams))
+		} else {
+			None
+		}
+	}
+
+	/// Objective area sent to clients, if the mode has one.
+	fn hill(&self) -> Option<Hill> {
+		None
+	}
+
+	fn scores(&self, player_wolf_counts: &HashMap<u8, usize>) -> HashMap<u8, u32>;
+}
+
+fn unique_leader(scores: Vec<(u8, u32)>) -> Option<u8> {
+	let max_score = scores.iter().map(|(_, score)| *score).max()?;
+	let mut leaders = scores.iter().filter(|(_, score)| *score == max_score);
+	match (leaders.next(), leaders.next()) {
+		(Some((key, _)), None) if max_score > 0 => Some(*key),
+		_ => None
+	}
+}
+
+/// Players sharing the highest non-zero score, or every member of the tied teams in team mode.
+pub fn tied_leaders(scores: &HashMap<u8, u32>, teams: &Teams) -> Vec<u8> {
+	let side_scores: Vec<(Vec<u8>, u32)> = if teams.enabled() {
+		(0..teams.team_count()).map(|team| {
+			let members = teams.members(team);
+			let score = members.iter().map(|pid| scores.get(pid).unwrap_or(&0)).sum();
+			(members, score)
+		}).collect()
+	} else {
+		scores.iter().map(|(pid, score)| (vec![*pid], *score)).collect()
+	};
+	let max_score = side_scores.iter().map(|(_, score)| *score).max().unwrap_or(0);
+	if max_score == 0 {
+		return Vec::new();
+	}
+	let mut tied: Vec<u8> = side_scores.into_iter().filter(|(_, score)| *score == max_score)
+		.flat_map(|(pids, _)| pids).collect();
+	tied.sort();
+	tied
+}
+
+/// Player with the single highest score, or None if tied.
+/// In team mode, the best player of the leading team is considered the leader.
+pub fn leading_player(scores: &HashMap<u8, u32>, teams: &Teams) -> Option<u8> {
+	if teams.enabled() {
+		let team = unique_leader((0..teams.team_count())
+			.map(|team| (team, teams.members(team).iter().map(|pid| scores.get(pid).unwrap_or(&0)).sum())).collect())?;
+		teams.members(team).into_iter().max_by_key(|pid| *scores.get(pid).unwrap_or(&0))
+	} else {
+		unique_leader(scores.iter().map(|(pid, score)| (*pid, *score)).collect())
+	}
+}
@@ -0,0 +1,31 @@
+use std::collections::HashMap;
+use crate::mode::{GameMode, GameModeKind, ModeContext};
+
+/// Players earn a point per wolf held every second, and the highest score when time runs out wins.
+pub struct TimedScore {
+	points: HashMap<u8, u32>
+}
+
+impl TimedScore {
+	pub fn new() -> Self {
+		Self {
+			points: HashMap::new()
+		}
+	}
+}
+
+impl GameMode for TimedScore {
+	fn kind(&self) -> GameModeKind {
+		GameModeKind::TimedScore
+	}
+
+	fn tick(&mut self, ctx: &ModeContext, _elapsed_secs: usize) {
+		for (pid, wolves) in ctx.packs.wolf_counts() {
+			*self.points.entry(pid).or_insert(0) += wolves as u32;
+		}
+	}
+
+	fn scores(&self, _player_wolf_counts: &HashMap<u8, usize>) -> HashMap<u8, u32> {
+		self.points.clone()
+	}
+}
@@ -2,10 +2,50 @@ use serde::{Serialize, Deserialize};
 use cubik::player::PlayerControlMessage;
 use crate::minipack::MiniPackUpdate;
 use crate::stage::GameStageUpdate;
+use crate::rules::MatchRules;
+use crate::team::Teams;
+use crate::powerup::{PowerUpEvent, PowerUpSnapshot};
 
 #[derive(Serialize, Deserialize)]
 pub enum AppMessage {
 	PlayerChange { player_id: u8, msg: PlayerControlMessage },
 	PackUpdate(Vec<MiniPackUpdate>),
-	StageChange(GameStageUpdate)
+	StageChange(GameStageUpdate),
+	ServerSay(String),
+	MapChange(String),
+	RulesChange(MatchRules),
+	ReadyToggle,
+	TeamsChange(Teams),
+	DropPack,
+	/// Number of neutral packs spawned by a respawn wave
+	PacksRespawned(u16),
+	PowerUp(PowerUpEvent),
+	PowerUpSnapshot(PowerUpSnapshot),
+	Howl,
+	/// Player who howled
+	Howled(u8),
+	/// Sent before a player is disconnected by the server
+	Kicked { player_id: u8, reason: String }
+}
+
+impl AppMessage {
+	pub fn name(&self) -> &'static str {
+		match self {
+			AppMessage::PlayerChange { .. } => "PlayerChange",
+			AppMessage::PackUpdate(_) => "PackUpdate",
+			AppMessage::StageChange(_) => "StageChange",
+			AppMessage::ServerSay(_) => "ServerSay",
+			AppMessage::MapChange(_) => "MapChange",
+			AppMessage::RulesChange(_) => "RulesChange",
+			AppMessage::ReadyToggle => "ReadyToggle",
+			AppMessage::TeamsChange(_) => "TeamsChange",
+			AppMessage::DropPack => "DropPack",
+			AppMessage::PacksRespawned(_) => "PacksRespawned",
+			AppMessage::PowerUp(_) => "PowerUp",
+			AppMessage::PowerUpSnapshot(_) => "PowerUpSnapshot",
+			AppMessage::Howl => "Howl",
+			AppMessage::Howled(_) => "Howled",
+			AppMessage::Kicked { .. } => "Kicked"
+		}
+	}
 }
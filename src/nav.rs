@@ -0,0 +1,290 @@
+use cubik::map::GameMap;
+use cubik::quadoctree::QuadOctreeNode;
+use cubik::collision::check_player_collision;
+use cubik::cube::generate_cube_collideobj;
+use cubik::math::normalize_vector;
+use std::collections::{BinaryHeap, HashMap};
+use std::cmp::Reverse;
+use log::info;
+
+const CELL_SIZE: f32 = 1.;
+const BOUNDS_MARGIN: f32 = 20.;
+const MAX_SEARCH_NODES: usize = 4000;
+const STRAIGHT_COST: u32 = 10;
+const DIAGONAL_COST: u32 = 14;
+
+/// True if something standing at the position would be inside a wall.
+/// Floors and ramps don't count, only steep surfaces.
+pub fn is_blocked(quadoctree: &QuadOctreeNode, position: (f32, f32), height: f32) -> bool {
+	let position = [position.0, height, position.1];
+	let collide_obj = generate_cube_collideobj(&[0., 0.75, 0.], &position, &[0.5, 0.5, 0.5], 0.);
+	let collide_result = check_player_collision(quadoctree, &position, &collide_obj);
+	collide_result.polygons.iter().any(|resolve| normalize_vector(resolve)[1].abs() < 0.5)
+}
+
+fn distance(a: (f32, f32), b: (f32, f32)) -> f32 {
+	((a.0 - b.0).powi(2) + (a.1 - b.1).powi(2)).sqrt()
+}
+
+/// Walkability grid over the map, sampled from the quadoctree at pack height.
+pub struct NavGrid {
+	origin: (f32, f32),
+	width: usize,
+	depth: usize,
+	walkable: Vec<bool>
+}
+
+impl NavGrid {
+	pub fn build(map: &GameMap, height: f32) -> Self {
+		let quadoctree = map.quadoctree.as_ref().unwrap();
+		let points: Vec<&[f32; 3]> = map.misc_objs.values().collect();
+		let min_x = points.iter().map(|p| p[0]).fold(0., f32::min) - BOUNDS_MARGIN;
+		let max_x = points.iter().map(|p| p[0]).fold(0., f32::max) + BOUNDS_MARGIN;
+		let min_z = points.iter().map(|p| p[2]).fold(0., f32::min) - BOUNDS_MARGIN;
+		let max_z = points.iter().map(|p| p[2]).fold(0., f32::max) + BOUNDS_MARGIN;
+
+		let width = ((max_x - min_x) / CELL_SIZE).ceil() as usize;
+		let depth = ((max_z - min_z) / CELL_SIZE).ceil() as usize;
+		let mut grid = Self {
+			origin: (min_x, min_z),
+			width: width,
+			depth: depth,
+			walkable: Vec::with_capacity(width * depth)
+		};
+		for z in 0..depth {
+			for x in 0..width {
+				let blocked = is_blocked(quadoctree, grid.cell_center((x, z)), height);
+				grid.walkable.push(!blocked);
+			}
+		}
+		info!("built {}x{} nav grid, {} cells blocked", width, depth, grid.walkable.iter().filter(|w| !**w).count());
+		grid
+	}
+
+	fn cell_of(&self, position: (f32, f32)) -> Option<(usize, usize)> {
+		let x = ((position.0 - self.origin.0) / CELL_SIZE).floor();
+		let z = ((position.1 - self.origin.1) / CELL_SIZE).floor();
+		if x < 0. || z < 0. || x as usize >= self.width || z as usize >= self.depth {
+			return None;
+		}
+		Some((x as usize, z as usize))
+	}
+
+	fn cell_center(&self, cell: (usize, usize)) -> (f32, f32) {
+		(self.origin.0 + (cell.0 as f32 + 0.5) * CELL_SIZE, self.origin.1 + (cell.1 as f32 + 0.5) * CELL_SIZE)
+	}
+
+	fn cell_walkable(&self, cell: (usize, usize)) -> bool {
+		self.walkable[cell.1 * self.width + cell.0]
+	}
+
+	/// Positions outside of the grid are treated as blocked
+	pub fn is_walkable(&self, position: (f32, f32)) -> bool {
+		self.cell_of(position).map(|cell| self.cell_walkable(cell)).unwrap_or(false)
+	}
+
+	/// Samples the segment every half cell
+	pub fn line_clear(&self, from: (f32, f32), to: (f32, f32)) -> bool {
+		let steps = (distance(from, to) / (CELL_SIZE * 0.5)).ceil() as usize;
+		(1..=steps).all(|i| {
+			let progress = i as f32 / steps as f32;
+			self.is_walkable((from.0 + (to.0 - from.0) * progress, from.1 + (to.1 - from.1) * progress))
+		})
+	}
+
+	fn neighbours(&self, cell: (usize, usize)) -> Vec<((usize, usize), u32)> {
+		let mut result = Vec::with_capacity(8);
+		for dz in -1i32..=1 {
+			for dx in -1i32..=1 {
+				if dx == 0 && dz == 0 { continue; }
+				let x = cell.0 as i32 + dx;
+				let z = cell.1 as i32 + dz;
+				if x < 0 || z < 0 || x as usize >= self.width || z as usize >= self.depth { continue; }
+				let next = (x as usize, z as usize);
+				if !self.cell_walkable(next) { continue; }
+				if dx != 0 && dz != 0 {
+					// don't cut corners of walls
+					if !self.cell_walkable((x as usize, cell.1)) || !self.cell_walkable((cell.0, z as usize)) { continue; }
+					result.push((next, DIAGONAL_COST));
+				} else {
+					result.push((next, STRAIGHT_COST));
+				}
+			}
+		}
+		result
+	}
+
+	fn heuristic(a: (usize, usize), b: (usize, usize)) -> u32 {
+		let dx = (a.0 as i32 - b.0 as i32).abs() as u32;
+		let dz = (a.1 as i32 - b.1 as i32).abs() as u32;
+		STRAIGHT_COST * (dx + dz) - (2 * STRAIGHT_COST - DIAGONAL_COST) * dx.min(dz)
+	}
+
+	/// A* search, returning the waypoints after `from` up to and including `to`.
+	/// Returns None if there is no path or the search gives up.
+	pub fn find_path(&self, from: (f32, f32), to: (f32, f32)) -> Option<Vec<(f32, f32)>> {
+		if self.line_clear(from, to) {
+			return Some(vec![to]);
+		}
+		let start = self.cell_of(from)?;
+		let goal = self.cell_of(to)?;
+		if !self.cell_walkable(goal) {
+			return None;
+		}
+
+		let mut open = BinaryHeap::new();
+		let mut came_from: HashMap<(usize, usize), (usize, usize)> = HashMap::new();
+		let mut costs: HashMap<(usize, usize), u32> = HashMap::new();
+		open.push(Reverse((Self::heuristic(start, goal), start)));
+		costs.insert(start, 0);
+
+		let mut searched = 0;
+		while let Some(Reverse((_, cell))) = open.pop() {
+			if cell == goal {
+				let mut cells = vec![goal];
+				while let Some(previous) = came_from.get(cells.last().unwrap()) {
+					cells.push(*previous);
+				}
+				cells.reverse();
+				return Some(self.smooth_path(from, &cells[1..], to));
+			}
+			searched += 1;
+			if searched > MAX_SEARCH_NODES {
+				return None;
+			}
+			let cost = costs[&cell];
+			for (next, step_cost) in self.neighbours(cell) {
+				let next_cost = cost + step_cost;
+				if costs.get(&next).map(|c| next_cost < *c).unwrap_or(true) {
+					costs.insert(next, next_cost);
+					came_from.insert(next, cell);
+					open.push(Reverse((next_cost + Self::heuristic(next, goal), next)));
+				}
+			}
+		}
+		None
+	}
+
+	/// Drops waypoints that can be skipped with a clear line of sight
+	fn smooth_path(&self, from: (f32, f32), cells: &[(usize, usize)], to: (f32, f32)) -> Vec<(f32, f32)> {
+		let mut points: Vec<(f32, f32)> = cells.iter().map(|cell| self.cell_center(*cell)).collect();
+		if let Some(last) = points.last_mut() {
+			*last = to;
+		}
+		let mut result = Vec::new();
+		let mut current = from;
+		let mut i = 0;
+		while i < points.len() {
+			let mut furthest = i;
+			for j in (i + 1)..points.len() {
+				if self.line_clear(current, points[j]) {
+					furthest = j;
+				}
+			}
+			current = points[furthest];
+			result.push(current);
+			i = furthest + 1;
+		}
+		result
+	}
+}
+
+/// Moves along a path until `remaining` of its length is left before the end,
+/// dropping waypoints that were passed. Returns the new position.
+pub fn advance_along(path: &mut Vec<(f32, f32)>, from: (f32, f32), remaining: f32) -> (f32, f32) {
+	let mut total = 0.;
+	let mut previous = from;
+	for point in path.iter() {
+		total += distance(previous, *point);
+		previous = *point;
+	}
+	let mut to_travel = total - remaining;
+	let mut position = from;
+	while to_travel > 0. && !path.is_empty() {
+		let segment = distance(position, path[0]);
+		if segment <= to_travel {
+			to_travel -= segment;
+			position = path.remove(0);
+		} else {
+			let progress = to_travel / segment;
+			position = (position.0 + (path[0].0 - position.0) * progress, position.1 + (path[0].1 - position.1) * progress);
+			to_travel = 0.;
+		}
+	}
+	position
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	/// Open 10x10 grid with one cell per unit, blocking the given cells
+	fn grid_with_blocked(blocked: &[(usize, usize)]) -> NavGrid {
+		let mut grid = NavGrid {
+			origin: (0., 0.),
+			width: 10,
+			depth: 10,
+			walkable: vec![true; 100]
+		};
+		for (x, z) in blocked {
+			grid.walkable[z * grid.width + x] = false;
+		}
+		grid
+	}
+
+	fn assert_close(a: (f32, f32), b: (f32, f32)) {
+		assert!(distance(a, b) < 0.001, "{:?} != {:?}", a, b);
+	}
+
+	#[test]
+	fn outside_is_blocked() {
+		let grid = grid_with_blocked(&[]);
+		assert!(grid.is_walkable((5., 5.)));
+		assert!(!grid.is_walkable((-1., 5.)));
+		assert!(!grid.is_walkable((5., 10.5)));
+	}
+
+	#[test]
+	fn path_detours_around_wall() {
+		// wall along x = 5 with a gap at the top
+		let wall: Vec<(usize, usize)> = (0..8).map(|z| (5, z)).collect();
+		let grid = grid_with_blocked(&wall);
+		let from = (2.5, 2.5);
+		let to = (7.5, 2.5);
+		assert!(!grid.line_clear(from, to));
+
+		let path = grid.find_path(from, to).expect("no path around the wall");
+		assert_close(*path.last().unwrap(), to);
+		assert!(path.iter().any(|point| point.1 >= 8.), "path doesn't use the gap: {:?}", path);
+		let mut previous = from;
+		for point in &path {
+			assert!(grid.line_clear(previous, *point), "segment {:?} -> {:?} crosses the wall", previous, point);
+			previous = *point;
+		}
+	}
+
+	#[test]
+	fn unreachable_goal() {
+		let wall: Vec<(usize, usize)> = (0..10).map(|z| (5, z)).collect();
+		let grid = grid_with_blocked(&wall);
+		assert!(grid.find_path((2.5, 2.5), (7.5, 2.5)).is_none());
+		// goal inside the wall
+		assert!(grid.find_path((2.5, 2.5), (5.5, 2.5)).is_none());
+	}
+
+	#[test]
+	fn advance_keeps_trailing_distance() {
+		let mut path = vec![(10., 0.)];
+		assert_close(advance_along(&mut path, (0., 0.), 3.), (7., 0.));
+		assert_eq!(path.len(), 1);
+
+		let mut path = vec![(5., 0.), (5., 5.)];
+		assert_close(advance_along(&mut path, (0., 0.), 2.), (5., 3.));
+		assert_eq!(path.len(), 1);
+
+		// already within the trailing distance
+		let mut path = vec![(1., 0.)];
+		assert_close(advance_along(&mut path, (0., 0.), 2.), (0., 0.));
+		assert_eq!(path.len(), 1);
+	}
+}
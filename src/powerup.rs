@@ -0,0 +1,303 @@
+use cubik::map::GameMap;
+use serde::{Serialize, Deserialize};
+use std::collections::{HashMap, HashSet};
+use rand::Rng;
+use log::info;
+
+const POWERUP_PREFIX: &str = "misc_powerup_";
+/// Seconds between full state snapshots, so players who join mid-round catch up
+const SNAPSHOT_INTERVAL: f32 = 1.;
+
+const ALL_KINDS: [PowerUpKind; 4] = [PowerUpKind::SpeedBurst, PowerUpKind::StealShield, PowerUpKind::Reveal, PowerUpKind::Magnet];
+
+#[derive(Serialize, Deserialize, Clone, Copy, PartialEq, Eq, Hash, Debug)]
+pub enum PowerUpKind {
+	SpeedBurst,
+	/// Owned packs can't be stolen
+	StealShield,
+	/// Shows every pack on the minimap
+	Reveal,
+	/// Widens the pickup range for neutral packs
+	Magnet
+}
+
+impl PowerUpKind {
+	/// Kind named by a map object such as `misc_powerup_magnet_1`
+	fn from_obj_name(name: &str) -> Option<Self> {
+		let suffix = &name[POWERUP_PREFIX.len()..];
+		ALL_KINDS.iter().find(|kind| suffix.starts_with(kind.key())).cloned()
+	}
+
+	fn key(&self) -> &'static str {
+		match self {
+			PowerUpKind::SpeedBurst => "speed",
+			PowerUpKind::StealShield => "shield",
+			PowerUpKind::Reveal => "reveal",
+			PowerUpKind::Magnet => "magnet"
+		}
+	}
+
+	pub fn name(&self) -> &'static str {
+		match self {
+			PowerUpKind::SpeedBurst => "Speed burst",
+			PowerUpKind::StealShield => "Steal shield",
+			PowerUpKind::Reveal => "Reveal",
+			PowerUpKind::Magnet => "Magnet"
+		}
+	}
+
+	pub fn icon(&self) -> &'static str {
+		match self {
+			PowerUpKind::SpeedBurst => "S",
+			PowerUpKind::StealShield => "D",
+			PowerUpKind::Reveal => "R",
+			PowerUpKind::Magnet => "M"
+		}
+	}
+
+	pub fn color(&self) -> [f32; 4] {
+		match self {
+			PowerUpKind::SpeedBurst => [0.3, 0.8, 1.0, 0.8],
+			PowerUpKind::StealShield => [0.3, 1.0, 0.4, 0.8],
+			PowerUpKind::Reveal => [1.0, 0.85, 0.2, 0.8],
+			PowerUpKind::Magnet => [1.0, 0.35, 0.8, 0.8]
+		}
+	}
+
+	fn seconds(&self, rules: &PowerUpRules) -> f32 {
+		match self {
+			PowerUpKind::SpeedBurst => rules.speed_seconds,
+			PowerUpKind::StealShield => rules.shield_seconds,
+			PowerUpKind::Reveal => rules.reveal_seconds,
+			PowerUpKind::Magnet => rules.magnet_seconds
+		}
+	}
+}
+
+#[derive(Serialize, Deserialize, Clone)]
+#[serde(default)]
+pub struct PowerUpRules {
+	pub enabled: bool,
+	pub pickup_distance: f32,
+	/// Seconds before a spot spawns another power-up after one was picked up
+	pub respawn_seconds: f32,
+	pub speed_multiplier: f32,
+	pub speed_seconds: f32,
+	pub shield_seconds: f32,
+	pub reveal_seconds: f32,
+	pub magnet_seconds: f32,
+	/// Pickup distance for neutral packs while the magnet is active
+	pub magnet_pickup_distance: f32
+}
+
+impl Default for PowerUpRules {
+	fn default() -> Self {
+		Self {
+			enabled: true,
+			pickup_distance: 1.5,
+			respawn_seconds: 30.,
+			speed_multiplier: 1.6,
+			speed_seconds: 5.,
+			shield_seconds: 8.,
+			reveal_seconds: 6.,
+			magnet_seconds: 10.,
+			magnet_pickup_distance: 5.
+		}
+	}
+}
+
+#[derive(Serialize, Deserialize, Clone)]
+pub enum PowerUpEvent {
+	Spawned { spot: u8, kind: PowerUpKind },
+	PickedUp { spot: u8, pid: u8, kind: PowerUpKind, seconds: f32 },
+	Expired { pid: u8, kind: PowerUpKind }
+}
+
+/// Full power-up state, sent periodically alongside the events
+#[derive(Serialize, Deserialize, Clone)]
+pub struct PowerUpSnapshot {
+	available: Vec<Option<PowerUpKind>>,
+	/// Player, kind, remaining and total seconds
+	active: Vec<(u8, PowerUpKind, f32, f32)>
+}
+
+struct PowerUpSpot {
+	position: [f32; 3],
+	/// Kind given by the map object name, random otherwise
+	named_kind: Option<PowerUpKind>,
+	available: Option<PowerUpKind>,
+	respawn_time: f32
+}
+
+/// Power-up spots and the power-ups each player has active.
+/// The server runs the timers, clients mirror them from `PowerUpEvent`s.
+pub struct PowerUps {
+	spots: Vec<PowerUpSpot>,
+	/// Remaining and total seconds of each active power-up, per player
+	active: HashMap<u8, HashMap<PowerUpKind, (f32, f32)>>,
+	spawned: bool,
+	snapshot_time_count: f32
+}
+
+impl PowerUps {
+	pub fn new() -> Self {
+		Self {
+			spots: Vec::new(),
+			active: HashMap::new(),
+			spawned: false,
+			snapshot_time_count: 0.
+		}
+	}
+
+	/// Spots are indexed in name order, so the server and clients agree on them
+	pub fn spawn(&mut self, map: &GameMap) {
+		let mut spot_keys: Vec<&String> = map.misc_objs.keys().filter(|k| k.starts_with(POWERUP_PREFIX)).collect();
+		spot_keys.sort();
+		self.spots = spot_keys.into_iter().map(|k| PowerUpSpot {
+			position: map.misc_objs[k],
+			named_kind: PowerUpKind::from_obj_name(k),
+			available: None,
+			respawn_time: 0.
+		}).collect();
+		self.active.clear();
+		self.spawned = true;
+	}
+
+	pub fn clear(&mut self) {
+		self.spots.clear();
+		self.active.clear();
+		self.spawned = false;
+	}
+
+	pub fn is_spawned(&self) -> bool {
+		self.spawned
+	}
+
+	pub fn remove_player(&mut self, pid: u8) {
+		self.active.remove(&pid);
+	}
+
+	pub fn is_active(&self, pid: u8, kind: PowerUpKind) -> bool {
+		self.active.get(&pid).map(|timers| timers.contains_key(&kind)).unwrap_or(false)
+	}
+
+	pub fn active_pids(&self, kind: PowerUpKind) -> HashSet<u8> {
+		self.active.iter().filter(|(_, timers)| timers.contains_key(&kind)).map(|(pid, _)| *pid).collect()
+	}
+
+	/// Positions of the spots that currently have a power-up, with its kind
+	pub fn available_spots(&self) -> Vec<([f32; 3], PowerUpKind)> {
+		self.spots.iter().filter_map(|spot| spot.available.map(|kind| (spot.position, kind))).collect()
+	}
+
+	/// Active power-ups of a player with the fraction of their duration left
+	pub fn remaining(&self, pid: u8) -> Vec<(PowerUpKind, f32)> {
+		let timers = match self.active.get(&pid) {
+			Some(timers) => timers,
+			None => return Vec::new()
+		};
+		ALL_KINDS.iter().filter_map(|kind| {
+			timers.get(kind).map(|(remaining, total)| (*kind, if *total > 0. { remaining / total } else { 0. }))
+		}).collect()
+	}
+
+	pub fn server_update(&mut self, time_delta: f32, players: &[(u8, [f32; 3])], rules: &PowerUpRules) -> Vec<PowerUpEvent> {
+		let mut events = Vec::new();
+		for (pid, timers) in self.active.iter_mut() {
+			for (kind, (remaining, _)) in timers.iter_mut() {
+				*remaining -= time_delta;
+				if *remaining <= 0. {
+					events.push(PowerUpEvent::Expired { pid: *pid, kind: *kind });
+				}
+			}
+			timers.retain(|_, (remaining, _)| *remaining > 0.);
+		}
+		self.active.retain(|_, timers| !timers.is_empty());
+
+		let mut rng = rand::thread_rng();
+		for (index, spot) in self.spots.iter_mut().enumerate() {
+			let kind = match spot.available {
+				Some(kind) => kind,
+				None => {
+					spot.respawn_time -= time_delta;
+					if spot.respawn_time <= 0. {
+						let kind = spot.named_kind.unwrap_or_else(|| ALL_KINDS[rng.gen_range(0..ALL_KINDS.len())]);
+						spot.available = Some(kind);
+						events.push(PowerUpEvent::Spawned { spot: index as u8, kind: kind });
+					}
+					continue;
+				}
+			};
+			let picker = players.iter().find(|(_, position)| {
+				((position[0] - spot.position[0]).powi(2) + (position[2] - spot.position[2]).powi(2)).sqrt() < rules.pickup_distance
+			});
+			if let Some((pid, _)) = picker {
+				let seconds = kind.seconds(rules);
+				spot.available = None;
+				spot.respawn_time = rules.respawn_seconds;
+				self.active.entry(*pid).or_default().insert(kind, (seconds, seconds));
+				info!("player {} picked up {}", pid, kind.name());
+				events.push(PowerUpEvent::PickedUp { spot: index as u8, pid: *pid, kind: kind, seconds: seconds });
+			}
+		}
+		events
+	}
+
+	pub fn server_snapshot(&mut self, time_delta: f32) -> Option<PowerUpSnapshot> {
+		self.snapshot_time_count += time_delta;
+		if self.snapshot_time_count < SNAPSHOT_INTERVAL {
+			return None;
+		}
+		self.snapshot_time_count = 0.;
+		Some(PowerUpSnapshot {
+			available: self.spots.iter().map(|spot| spot.available).collect(),
+			active: self.active.iter().flat_map(|(pid, timers)| {
+				timers.iter().map(move |(kind, (remaining, total))| (*pid, *kind, *remaining, *total))
+			}).collect()
+		})
+	}
+
+	/// Ignored until the spots are spawned for the current round
+	pub fn client_snapshot(&mut self, snapshot: PowerUpSnapshot) {
+		if !self.spawned || snapshot.available.len() != self.spots.len() {
+			return;
+		}
+		for (spot, available) in self.spots.iter_mut().zip(snapshot.available) {
+			spot.available = available;
+		}
+		self.active.clear();
+		for (pid, kind, remaining, total) in snapshot.active {
+			self.active.entry(pid).or_default().insert(kind, (remaining, total));
+		}
+	}
+
+	pub fn client_event(&mut self, event: PowerUpEvent) {
+		match event {
+			PowerUpEvent::Spawned { spot, kind } => {
+				if let Some(spot) = self.spots.get_mut(spot as usize) {
+					spot.available = Some(kind);
+				}
+			},
+			PowerUpEvent::PickedUp { spot, pid, kind, seconds } => {
+				if let Some(spot) = self.spots.get_mut(spot as usize) {
+					spot.available = None;
+				}
+				self.active.entry(pid).or_default().insert(kind, (seconds, seconds));
+			},
+			PowerUpEvent::Expired { pid, kind } => {
+				if let Some(timers) = self.active.get_mut(&pid) {
+					timers.remove(&kind);
+				}
+			}
+		}
+	}
+
+	/// Counts down durations for the HUD, expiry is left to the server
+	pub fn client_update(&mut self, time_delta: f32) {
+		for timers in self.active.values_mut() {
+			for (remaining, _) in timers.values_mut() {
+				*remaining = (*remaining - time_delta).max(0.);
+			}
+		}
+	}
+}
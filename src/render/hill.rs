@@ -0,0 +1,89 @@
+use cubik::glium::{Display, Program, Frame, Surface};
+use cubik::fonts::{LoadedFont, FontText, FontError, TextAlign};
+use crate::mode::Hill;
+
+const MARKER_TEXT_SIZE: f32 = 0.07;
+const MARKER_Y: f32 = 0.5;
+const MARKER_EDGE_X: f32 = 1.5;
+const MARKER_COLOR: [f32; 4] = [1.0, 0.85, 0.2, 1.0];
+
+/// HUD marker that points towards the active hill.
+pub struct HillMarker {
+	content: String,
+	drawn_content: String,
+	text: Option<FontText>
+}
+
+impl HillMarker {
+	pub fn new() -> Self {
+		Self {
+			content: String::new(),
+			drawn_content: String::new(),
+			text: None
+		}
+	}
+
+	/// Horizontal UI position of the hill, and whether it's clamped to the screen edge
+	fn screen_x(hill: &Hill, view_mat: &[[f32; 4]; 4], perspective_mat: &[[f32; 4]; 4], aspect: f32) -> (f32, bool) {
+		let (x, z) = hill.center;
+		let view_x = view_mat[0][0] * x + view_mat[2][0] * z + view_mat[3][0];
+		let view_z = view_mat[0][2] * x + view_mat[2][2] * z + view_mat[3][2];
+		if view_z < 0. {
+			let screen_x = perspective_mat[0][0] * view_x / -view_z * aspect;
+			if screen_x.abs() <= MARKER_EDGE_X {
+				return (screen_x, false);
+			}
+		}
+		(MARKER_EDGE_X.copysign(view_x), true)
+	}
+
+	pub fn update(&mut self, hill: Option<&Hill>, player_position: &[f32; 3]) {
+		let hill = match hill {
+			Some(hill) => hill,
+			None => {
+				self.content.clear();
+				self.text = None;
+				return;
+			}
+		};
+		let x = player_position[0] - hill.center.0;
+		let z = player_position[2] - hill.center.1;
+		let distance = (x * x + z * z).sqrt();
+		let mut content = if distance <= hill.radius {
+			"On the hill".to_string()
+		} else {
+			format!("Hill {}m", distance.round() as u32)
+		};
+		if let Some(seconds) = hill.seconds_to_rotate {
+			content = format!("{} - moves in {}s", content, seconds);
+		}
+		if content != self.content {
+			let mut text = FontText::new(content.clone(), MARKER_TEXT_SIZE, (0., MARKER_Y), TextAlign::Center);
+			text.ui_draw_info.color = MARKER_COLOR;
+			self.text = Some(text);
+			self.content = content;
+		}
+	}
+
+	pub fn draw(&mut self, target: &mut Frame, display: &Display, program: &Program, font: &LoadedFont,
+		hill: Option<&Hill>, view_mat: &[[f32; 4]; 4], perspective_mat: &[[f32; 4]; 4]) -> Result<(), FontError> {
+		let (text, hill) = match (self.text.as_mut(), hill) {
+			(Some(text), Some(hill)) => (text, hill),
+			_ => return Ok(())
+		};
+		let (width, height) = target.get_dimensions();
+		let (screen_x, clamped) = Self::screen_x(hill, view_mat, perspective_mat, width as f32 / height as f32);
+		// when the hill is off screen, stick to the nearest edge and point towards it
+		let (content, align) = match (clamped, screen_x < 0.) {
+			(false, _) => (self.content.clone(), TextAlign::Center),
+			(true, true) => (format!("< {}", self.content), TextAlign::Left),
+			(true, false) => (format!("{} >", self.content), TextAlign::Right)
+		};
+		if (text.ui_draw_info.position.0 - screen_x).abs() > 0.01 || self.drawn_content != content {
+			*text = FontText::new(content.clone(), MARKER_TEXT_SIZE, (screen_x, MARKER_Y), align);
+			text.ui_draw_info.color = MARKER_COLOR;
+			self.drawn_content = content;
+		}
+		text.draw(target, display, program, font)
+	}
+}
@@ -0,0 +1,127 @@
+use cubik::glium::{Display, Program, Frame, Surface, VertexBuffer, DrawParameters, Blend};
+use cubik::glium::index::{NoIndices, PrimitiveType};
+use cubik::glium::{implement_vertex, uniform};
+use crate::render::RenderError;
+use crate::minipack::MiniPacks;
+use crate::team::Teams;
+
+const MINIMAP_CENTER: (f32, f32) = (-1.45, -0.65);
+const MINIMAP_HALF_SIZE: f32 = 0.28;
+/// World distance from the player to the edge of the minimap
+const MINIMAP_RANGE: f32 = 50.;
+const DOT_HALF_SIZE: f32 = 0.012;
+const SQUARE_VERTICES: usize = 6;
+/// Squares the vertex buffer fits before it has to grow
+const INITIAL_SQUARE_CAPACITY: usize = 64;
+
+const BACKGROUND_COLOR: [f32; 4] = [0.1, 0.1, 0.1, 0.4];
+const PLAYER_COLOR: [f32; 4] = [1.0, 1.0, 1.0, 1.0];
+const NEUTRAL_COLOR: [f32; 4] = [0.8, 0.8, 0.8, 0.9];
+
+const MINIMAP_VERTEX_SHADER: &str = r#"
+	#version 140
+
+	in vec2 position;
+	in vec4 color;
+
+	uniform float aspect;
+
+	out vec4 v_color;
+
+	void main() {
+		gl_Position = vec4(position.x / aspect, position.y, 0.0, 1.0);
+		v_color = color;
+	}
+"#;
+
+const MINIMAP_FRAGMENT_SHADER: &str = r#"
+	#version 140
+
+	in vec4 v_color;
+	out vec4 f_color;
+
+	void main() {
+		f_color = v_color;
+	}
+"#;
+
+#[derive(Copy, Clone)]
+struct MinimapVertex {
+	position: [f32; 2],
+	color: [f32; 4]
+}
+
+implement_vertex!(MinimapVertex, position, color);
+
+/// Top down view around the player in the bottom left corner, with north up.
+/// Shows the player's own and teammates' packs, or every pack while revealed.
+pub struct Minimap {
+	program: Program,
+	/// Rewritten every frame, and only reallocated when there are more dots than it fits
+	vertex_buffer: VertexBuffer<MinimapVertex>,
+	vertices: Vec<MinimapVertex>
+}
+
+impl Minimap {
+	pub fn new(display: &Display) -> Result<Self, RenderError> {
+		Ok(Self {
+			program: Program::from_source(display, MINIMAP_VERTEX_SHADER, MINIMAP_FRAGMENT_SHADER, None)?,
+			vertex_buffer: VertexBuffer::empty_dynamic(display, INITIAL_SQUARE_CAPACITY * SQUARE_VERTICES)?,
+			vertices: Vec::with_capacity(INITIAL_SQUARE_CAPACITY * SQUARE_VERTICES)
+		})
+	}
+
+	fn push_square(vertices: &mut Vec<MinimapVertex>, center: (f32, f32), half_size: f32, color: [f32; 4]) {
+		let corners = [(-1., -1.), (1., -1.), (1., 1.), (-1., -1.), (1., 1.), (-1., 1.)];
+		for (x, y) in corners.iter() {
+			vertices.push(MinimapVertex { position: [center.0 + x * half_size, center.1 + y * half_size], color: color });
+		}
+	}
+
+	pub fn draw(&mut self, target: &mut Frame, display: &Display, player_position: &[f32; 3], player_id: Option<u8>,
+		packs: &MiniPacks, teams: &Teams, reveal: bool) -> Result<(), RenderError> {
+		let vertices = &mut self.vertices;
+		vertices.clear();
+		Self::push_square(vertices, MINIMAP_CENTER, MINIMAP_HALF_SIZE, BACKGROUND_COLOR);
+
+		let scale = MINIMAP_HALF_SIZE / MINIMAP_RANGE;
+		for pack in &packs.packs {
+			let own_side = match (pack.owner, player_id) {
+				(Some(owner), Some(pid)) => teams.are_teammates(owner, pid),
+				_ => false
+			};
+			if !own_side && !reveal { continue; }
+			let x = pack.position.0 - player_position[0];
+			let z = pack.position.1 - player_position[2];
+			if x.abs() > MINIMAP_RANGE || z.abs() > MINIMAP_RANGE { continue; }
+			let color = match pack.owner {
+				Some(owner) => {
+					let color = teams.color(owner);
+					[color[0], color[1], color[2], 1.]
+				},
+				None => NEUTRAL_COLOR
+			};
+			Self::push_square(vertices, (MINIMAP_CENTER.0 + x * scale, MINIMAP_CENTER.1 - z * scale), DOT_HALF_SIZE, color);
+		}
+		Self::push_square(vertices, MINIMAP_CENTER, DOT_HALF_SIZE * 1.5, PLAYER_COLOR);
+
+		if vertices.len() > self.vertex_buffer.len() {
+			self.vertex_buffer = VertexBuffer::empty_dynamic(display, vertices.len().next_power_of_two())?;
+		}
+		let vertex_slice = match self.vertex_buffer.slice(0..vertices.len()) {
+			Some(vertex_slice) => vertex_slice,
+			None => return Ok(())
+		};
+		vertex_slice.write(vertices);
+
+		let (width, height) = target.get_dimensions();
+		let params = DrawParameters {
+			blend: Blend::alpha_blending(),
+			..Default::default()
+		};
+		target.draw(vertex_slice, &NoIndices(PrimitiveType::TrianglesList), &self.program, &uniform! {
+			aspect: width as f32 / height as f32
+		}, &params)?;
+		Ok(())
+	}
+}
@@ -0,0 +1,26 @@
+//! Client side drawing of the HUD and world overlays, kept apart from the game rules.
+
+mod ring;
+mod zone;
+mod hill;
+mod powerup;
+mod minimap;
+
+use cubik::glium::{ProgramCreationError, DrawError};
+use cubik::glium::vertex::BufferCreationError;
+use cubik::fonts::FontError;
+use derive_more::{From, Error};
+
+pub use self::ring::ProgressRing;
+pub use self::zone::ZoneRenderer;
+pub use self::hill::HillMarker;
+pub use self::powerup::PowerUpRenderer;
+pub use self::minimap::Minimap;
+
+#[derive(From, Error, derive_more::Display, Debug)]
+pub enum RenderError {
+	ProgramError(ProgramCreationError),
+	BufferError(BufferCreationError),
+	DrawError(DrawError),
+	FontError(FontError)
+}
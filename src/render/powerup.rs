@@ -0,0 +1,130 @@
+use cubik::glium::{Display, Program, Frame, Surface, VertexBuffer, DrawParameters, Depth, DepthTest, Blend};
+use cubik::glium::index::{NoIndices, PrimitiveType};
+use cubik::glium::{implement_vertex, uniform};
+use cubik::fonts::{LoadedFont, FontText, TextAlign};
+use cubik::draw::EnvDrawInfo;
+use std::collections::HashMap;
+use crate::powerup::{PowerUps, PowerUpKind};
+use crate::render::{RenderError, ProgressRing};
+
+const PILLAR_SEGMENTS: usize = 16;
+const PILLAR_RADIUS: f32 = 0.35;
+const PILLAR_HEIGHT: f32 = 1.2;
+const PILLAR_BOB_HEIGHT: f32 = 0.2;
+const PILLAR_BOB_SPEED: f32 = 2.;
+
+const ICON_RADIUS: f32 = 0.07;
+const ICON_SPACING: f32 = 0.2;
+const ICON_POSITION: (f32, f32) = (1.55, -0.82);
+const ICON_TEXT_SIZE: f32 = 0.06;
+
+const PILLAR_VERTEX_SHADER: &str = r#"
+	#version 140
+
+	in vec3 position;
+
+	uniform mat4 perspective;
+	uniform mat4 view;
+	uniform mat4 model;
+
+	void main() {
+		gl_Position = perspective * view * model * vec4(position, 1.0);
+	}
+"#;
+
+const PILLAR_FRAGMENT_SHADER: &str = r#"
+	#version 140
+
+	uniform vec4 color;
+	out vec4 f_color;
+
+	void main() {
+		f_color = color;
+	}
+"#;
+
+#[derive(Copy, Clone)]
+struct PillarVertex {
+	position: [f32; 3]
+}
+
+implement_vertex!(PillarVertex, position);
+
+/// Draws available power-ups as floating pillars, and the local player's active power-ups as HUD icons.
+pub struct PowerUpRenderer {
+	program: Program,
+	pillar: VertexBuffer<PillarVertex>,
+	time_count: f32,
+	icon_texts: HashMap<PowerUpKind, FontText>
+}
+
+impl PowerUpRenderer {
+	pub fn new(display: &Display) -> Result<Self, RenderError> {
+		let mut vertices = Vec::with_capacity((PILLAR_SEGMENTS + 1) * 2);
+		for i in 0..=PILLAR_SEGMENTS {
+			let angle = (i as f32 / PILLAR_SEGMENTS as f32) * std::f32::consts::PI * 2.;
+			vertices.push(PillarVertex { position: [angle.cos(), 0., angle.sin()] });
+			vertices.push(PillarVertex { position: [angle.cos(), 1., angle.sin()] });
+		}
+
+		Ok(Self {
+			program: Program::from_source(display, PILLAR_VERTEX_SHADER, PILLAR_FRAGMENT_SHADER, None)?,
+			pillar: VertexBuffer::new(display, &vertices)?,
+			time_count: 0.,
+			icon_texts: HashMap::new()
+		})
+	}
+
+	pub fn update(&mut self, time_delta: f32) {
+		self.time_count += time_delta;
+	}
+
+	pub fn draw_world(&self, target: &mut Frame, env_info: &EnvDrawInfo, powerups: &PowerUps) -> Result<(), RenderError> {
+		let params = DrawParameters {
+			depth: Depth {
+				test: DepthTest::IfLess,
+				write: true,
+				..Default::default()
+			},
+			blend: Blend::alpha_blending(),
+			..Default::default()
+		};
+		let bob = (self.time_count * PILLAR_BOB_SPEED).sin() * PILLAR_BOB_HEIGHT;
+		for (position, kind) in powerups.available_spots() {
+			let model: [[f32; 4]; 4] = [
+				[PILLAR_RADIUS, 0., 0., 0.],
+				[0., PILLAR_HEIGHT, 0., 0.],
+				[0., 0., PILLAR_RADIUS, 0.],
+				[position[0], position[1] + bob, position[2], 1.]
+			];
+			target.draw(&self.pillar, &NoIndices(PrimitiveType::TriangleStrip), &self.program, &uniform! {
+				perspective: env_info.perspective_mat,
+				view: env_info.view_mat,
+				model: model,
+				color: kind.color()
+			}, &params)?;
+		}
+		Ok(())
+	}
+
+	/// Icons stack leftwards from the bottom right corner, each ring showing the time left
+	pub fn draw_hud(&mut self, target: &mut Frame, display: &Display, program: &Program, font: &LoadedFont,
+		ring: &ProgressRing, powerups: &PowerUps, player_id: Option<u8>) -> Result<(), RenderError> {
+		let remaining = player_id.map(|pid| powerups.remaining(pid)).unwrap_or_default();
+		self.icon_texts.retain(|kind, _| remaining.iter().any(|(active, _)| active == kind));
+		for (i, (kind, progress)) in remaining.into_iter().enumerate() {
+			let center = (ICON_POSITION.0 - i as f32 * ICON_SPACING, ICON_POSITION.1);
+			ring.draw(target, center, ICON_RADIUS, progress, kind.color())?;
+
+			let text_position = (center.0, center.1 + ICON_TEXT_SIZE / 2.);
+			let text = self.icon_texts.entry(kind).or_insert_with(|| {
+				FontText::new(kind.icon().to_string(), ICON_TEXT_SIZE, text_position, TextAlign::Center)
+			});
+			if (text.ui_draw_info.position.0 - text_position.0).abs() > 0.01 {
+				*text = FontText::new(kind.icon().to_string(), ICON_TEXT_SIZE, text_position, TextAlign::Center);
+			}
+			text.draw(target, display, program, font)?;
+		}
+		Ok(())
+	}
+}
@@ -0,0 +1,97 @@
+use cubik::glium::{Display, Program, Frame, Surface, VertexBuffer, DrawParameters, Blend};
+use cubik::glium::index::{NoIndices, PrimitiveType};
+use cubik::glium::{implement_vertex, uniform};
+use crate::render::RenderError;
+
+const RING_SEGMENTS: usize = 48;
+const RING_INNER_RADIUS: f32 = 0.75;
+const BACKGROUND_COLOR: [f32; 4] = [0.1, 0.1, 0.1, 0.4];
+
+const RING_VERTEX_SHADER: &str = r#"
+	#version 140
+
+	in vec2 position;
+	in float fraction;
+
+	uniform vec2 center;
+	uniform float radius;
+	uniform float aspect;
+
+	out float v_fraction;
+
+	void main() {
+		vec2 ui_position = center + position * radius;
+		gl_Position = vec4(ui_position.x / aspect, ui_position.y, 0.0, 1.0);
+		v_fraction = fraction;
+	}
+"#;
+
+const RING_FRAGMENT_SHADER: &str = r#"
+	#version 140
+
+	in float v_fraction;
+
+	uniform float progress;
+	uniform vec4 color;
+	out vec4 f_color;
+
+	void main() {
+		if (v_fraction > progress) {
+			discard;
+		}
+		f_color = color;
+	}
+"#;
+
+#[derive(Copy, Clone)]
+struct RingVertex {
+	position: [f32; 2],
+	fraction: f32
+}
+
+implement_vertex!(RingVertex, position, fraction);
+
+/// Circular progress indicator drawn in UI coordinates, filling clockwise from the top.
+pub struct ProgressRing {
+	program: Program,
+	ring: VertexBuffer<RingVertex>
+}
+
+impl ProgressRing {
+	pub fn new(display: &Display) -> Result<Self, RenderError> {
+		let mut vertices = Vec::with_capacity((RING_SEGMENTS + 1) * 2);
+		for i in 0..=RING_SEGMENTS {
+			let fraction = i as f32 / RING_SEGMENTS as f32;
+			let angle = std::f32::consts::FRAC_PI_2 - fraction * std::f32::consts::PI * 2.;
+			vertices.push(RingVertex { position: [angle.cos() * RING_INNER_RADIUS, angle.sin() * RING_INNER_RADIUS], fraction: fraction });
+			vertices.push(RingVertex { position: [angle.cos(), angle.sin()], fraction: fraction });
+		}
+
+		Ok(Self {
+			program: Program::from_source(display, RING_VERTEX_SHADER, RING_FRAGMENT_SHADER, None)?,
+			ring: VertexBuffer::new(display, &vertices)?
+		})
+	}
+
+	fn draw_fill(&self, target: &mut Frame, center: (f32, f32), radius: f32, progress: f32, color: [f32; 4]) -> Result<(), RenderError> {
+		let (width, height) = target.get_dimensions();
+		let params = DrawParameters {
+			blend: Blend::alpha_blending(),
+			..Default::default()
+		};
+		target.draw(&self.ring, &NoIndices(PrimitiveType::TriangleStrip), &self.program, &uniform! {
+			center: [center.0, center.1],
+			radius: radius,
+			aspect: width as f32 / height as f32,
+			progress: progress,
+			color: color
+		}, &params)?;
+		Ok(())
+	}
+
+	/// Progress is clamped between 0 and 1
+	pub fn draw(&self, target: &mut Frame, center: (f32, f32), radius: f32, progress: f32, color: [f32; 4]) -> Result<(), RenderError> {
+		self.draw_fill(target, center, radius, 1., BACKGROUND_COLOR)?;
+		self.draw_fill(target, center, radius, progress.max(0.).min(1.), color)
+	}
+}
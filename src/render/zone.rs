@@ -0,0 +1,155 @@
+use cubik::glium::{Display, Program, Frame, Surface, VertexBuffer, DrawParameters, Depth, DepthTest, Blend};
+use cubik::glium::index::{NoIndices, PrimitiveType};
+use cubik::glium::{implement_vertex, uniform};
+use cubik::fonts::{LoadedFont, FontText, FontError, TextAlign};
+use cubik::draw::EnvDrawInfo;
+use crate::zone::SafeZone;
+use crate::render::RenderError;
+
+const WALL_SEGMENTS: usize = 64;
+const WALL_BOTTOM: f32 = -10.;
+const WALL_HEIGHT: f32 = 40.;
+const WALL_COLOR: [f32; 4] = [0.3, 0.5, 1.0, 0.25];
+const RADIUS_SMOOTHING: f32 = 2.;
+
+const HUD_TEXT_SIZE: f32 = 0.07;
+const HUD_COLOR: [f32; 4] = [0.6, 0.75, 1.0, 1.0];
+const WARNING_COLOR: [f32; 4] = [1.0, 0.25, 0.25, 1.0];
+
+const WALL_VERTEX_SHADER: &str = r#"
+	#version 140
+
+	in vec3 position;
+
+	uniform mat4 perspective;
+	uniform mat4 view;
+	uniform mat4 model;
+
+	void main() {
+		gl_Position = perspective * view * model * vec4(position, 1.0);
+	}
+"#;
+
+const WALL_FRAGMENT_SHADER: &str = r#"
+	#version 140
+
+	uniform vec4 color;
+	out vec4 f_color;
+
+	void main() {
+		f_color = color;
+	}
+"#;
+
+#[derive(Copy, Clone)]
+struct WallVertex {
+	position: [f32; 3]
+}
+
+implement_vertex!(WallVertex, position);
+
+pub struct ZoneRenderer {
+	program: Program,
+	wall: VertexBuffer<WallVertex>,
+	display_radius: Option<f32>,
+	hud_content: String,
+	hud_text: Option<FontText>,
+	warning_text: FontText,
+	outside: bool
+}
+
+impl ZoneRenderer {
+	pub fn new(display: &Display) -> Result<Self, RenderError> {
+		// unit cylinder, scaled to the zone radius when drawn
+		let mut vertices = Vec::with_capacity((WALL_SEGMENTS + 1) * 2);
+		for i in 0..=WALL_SEGMENTS {
+			let angle = (i as f32 / WALL_SEGMENTS as f32) * std::f32::consts::PI * 2.;
+			vertices.push(WallVertex { position: [angle.cos(), 0., angle.sin()] });
+			vertices.push(WallVertex { position: [angle.cos(), 1., angle.sin()] });
+		}
+
+		let mut warning_text = FontText::new("Outside the zone!".to_string(), HUD_TEXT_SIZE, (0., 0.6), TextAlign::Center);
+		warning_text.ui_draw_info.color = WARNING_COLOR;
+
+		Ok(Self {
+			program: Program::from_source(display, WALL_VERTEX_SHADER, WALL_FRAGMENT_SHADER, None)?,
+			wall: VertexBuffer::new(display, &vertices)?,
+			display_radius: None,
+			hud_content: String::new(),
+			hud_text: None,
+			warning_text: warning_text,
+			outside: false
+		})
+	}
+
+	pub fn update(&mut self, zone: Option<&SafeZone>, player_position: &[f32; 3], time_delta: f32) {
+		let zone = match zone {
+			Some(zone) => zone,
+			None => {
+				self.display_radius = None;
+				self.hud_text = None;
+				self.outside = false;
+				return;
+			}
+		};
+
+		// zone updates arrive once a second, so ease towards the latest radius
+		let display_radius = self.display_radius.unwrap_or(zone.radius);
+		self.display_radius = Some(display_radius + (zone.radius - display_radius) * (time_delta * RADIUS_SMOOTHING).min(1.));
+		self.outside = !zone.contains(player_position);
+
+		let hud_content = if zone.shrinking {
+			"Zone shrinking!".to_string()
+		} else if zone.target_radius < zone.radius {
+			format!("Zone shrinks in {:0>2}:{:0>2}", zone.seconds_to_shrink / 60, zone.seconds_to_shrink % 60)
+		} else {
+			"Final zone".to_string()
+		};
+		if hud_content != self.hud_content {
+			let mut text = FontText::new(hud_content.clone(), HUD_TEXT_SIZE, (0., 0.7), TextAlign::Center);
+			text.ui_draw_info.color = HUD_COLOR;
+			self.hud_text = Some(text);
+			self.hud_content = hud_content;
+		}
+	}
+
+	pub fn draw_wall(&self, target: &mut Frame, env_info: &EnvDrawInfo, zone: Option<&SafeZone>) -> Result<(), RenderError> {
+		let (zone, radius) = match (zone, self.display_radius) {
+			(Some(zone), Some(radius)) => (zone, radius),
+			_ => return Ok(())
+		};
+		let model: [[f32; 4]; 4] = [
+			[radius, 0., 0., 0.],
+			[0., WALL_HEIGHT, 0., 0.],
+			[0., 0., radius, 0.],
+			[zone.center.0, WALL_BOTTOM, zone.center.1, 1.]
+		];
+		let params = DrawParameters {
+			depth: Depth {
+				test: DepthTest::IfLess,
+				write: false,
+				..Default::default()
+			},
+			blend: Blend::alpha_blending(),
+			..Default::default()
+		};
+		target.draw(&self.wall, &NoIndices(PrimitiveType::TriangleStrip), &self.program, &uniform! {
+			perspective: env_info.perspective_mat,
+			view: env_info.view_mat,
+			model: model,
+			color: WALL_COLOR
+		}, &params)?;
+		Ok(())
+	}
+
+	pub fn draw_hud(&mut self, target: &mut Frame, display: &Display, program: &Program,
+		font: &LoadedFont) -> Result<(), FontError> {
+		if let Some(text) = self.hud_text.as_mut() {
+			text.draw(target, display, program, font)?;
+		}
+		if self.outside {
+			self.warning_text.draw(target, display, program, font)?;
+		}
+		Ok(())
+	}
+}
@@ -0,0 +1,118 @@
+use cubik::fonts::{LoadedFont, FontText, FontError, TextAlign};
+use cubik::glium::{Display, Program, Frame};
+use cubik::math::mult_vector;
+use crate::stage::PlayerStanding;
+use crate::series::SeriesStanding;
+use crate::team::Teams;
+
+const TITLE_TEXT_SIZE: f32 = 0.15;
+const FIRST_TEXT_SIZE: f32 = 0.11;
+const PODIUM_TEXT_SIZE: f32 = 0.09;
+const STATS_TEXT_SIZE: f32 = 0.065;
+const STATS_START_Y: f32 = 0.;
+
+const HEADER_COLOR: [f32; 4] = [1.0, 1.0, 1.0, 1.0];
+const TITLE_COLOR: [f32; 4] = [1.0, 0.25, 0.25, 1.0];
+
+const STATS_COLUMNS_X: [f32; 4] = [-0.9, 0.1, 0.45, 0.8];
+
+const PODIUM_POSITIONS: [((f32, f32), f32); 3] = [
+	((0., 0.38), FIRST_TEXT_SIZE),
+	((-0.7, 0.26), PODIUM_TEXT_SIZE),
+	((0.7, 0.2), PODIUM_TEXT_SIZE)
+];
+const PODIUM_LABELS: [&str; 3] = ["1st", "2nd", "3rd"];
+
+pub struct ResultsScreen {
+	texts: Vec<FontText>
+}
+
+impl ResultsScreen {
+	fn standing_color(pid: u8, teams: &Teams) -> [f32; 4] {
+		let color = mult_vector(&teams.color(pid), 0.9);
+		[color[0], color[1], color[2], 1.]
+	}
+
+	fn text(content: String, size: f32, position: (f32, f32), align: TextAlign, color: [f32; 4]) -> FontText {
+		let mut text = FontText::new(content, size, position, align);
+		text.ui_draw_info.color = color;
+		text
+	}
+
+	fn stats_row(texts: &mut Vec<FontText>, columns: [String; 4], y: f32, color: [f32; 4]) {
+		for (i, content) in columns.iter().enumerate() {
+			// name column is left aligned, stat columns are right aligned
+			let align = if i == 0 { TextAlign::Left } else { TextAlign::Right };
+			texts.push(Self::text(content.clone(), STATS_TEXT_SIZE, (STATS_COLUMNS_X[i], y), align, color));
+		}
+	}
+
+	fn winner_title(winner: Option<u8>, standings: &[PlayerStanding], teams: &Teams, suffix: &str) -> Option<String> {
+		let winner = winner?;
+		match teams.team_of(winner) {
+			Some(team) => Some(format!("Team {} wins {}!", Teams::team_name(team), suffix)),
+			None => standings.iter().find(|s| s.pid == winner).map(|s| format!("{} wins {}!", s.name, suffix))
+		}
+	}
+
+	pub fn new(winner: Option<u8>, winning_team: Option<u8>, standings: &[PlayerStanding], series: Option<&SeriesStanding>,
+		teams: &Teams, uses_points: bool) -> Self {
+		let mut texts = Vec::new();
+
+		let title = match series {
+			Some(series) if series.match_over => Self::winner_title(series.match_winner, standings, teams, "the match")
+				.unwrap_or_else(|| "Match tied".to_string()),
+			Some(series) => format!("Round {}: {}", series.round.round,
+				Self::winner_title(winner, standings, teams, "the round").unwrap_or_else(|| "no winner".to_string())),
+			None => match (winning_team, winner.and_then(|pid| standings.iter().find(|s| s.pid == pid))) {
+				(Some(team), _) => format!("Team {} wins!", Teams::team_name(team)),
+				(None, Some(standing)) => format!("{} wins!", standing.name),
+				(None, None) => "Game Over".to_string()
+			}
+		};
+		texts.push(Self::text(title, TITLE_TEXT_SIZE, (0., 0.6), TextAlign::Center, TITLE_COLOR));
+
+		if let Some(series) = series {
+			let wins: Vec<String> = series.round_wins.iter().filter_map(|(pid, wins)| {
+				standings.iter().find(|s| s.pid == *pid).map(|s| format!("{} {}", s.name, wins))
+			}).collect();
+			let content = if wins.is_empty() {
+				format!("No round wins after {} of {} rounds", series.round.round, series.round.rounds)
+			} else {
+				format!("Round wins: {}", wins.join(", "))
+			};
+			texts.push(Self::text(content, STATS_TEXT_SIZE, (0., 0.5), TextAlign::Center, HEADER_COLOR));
+		}
+
+		for (i, standing) in standings.iter().take(PODIUM_POSITIONS.len()).enumerate() {
+			let (position, size) = PODIUM_POSITIONS[i];
+			texts.push(Self::text(format!("{} {}", PODIUM_LABELS[i], standing.name), size, position,
+				TextAlign::Center, Self::standing_color(standing.pid, teams)));
+		}
+
+		Self::stats_row(&mut texts, [
+			"Player".to_string(), if uses_points { "Points" } else { "Wolves" }.to_string(),
+			"Captures".to_string(), "Steals".to_string()
+		], STATS_START_Y, HEADER_COLOR);
+		for (i, standing) in standings.iter().enumerate() {
+			Self::stats_row(&mut texts, [
+				format!("{}. {}", i + 1, standing.name),
+				if uses_points { standing.score as usize } else { standing.wolves }.to_string(),
+				standing.captures.to_string(),
+				standing.steals.to_string()
+			], STATS_START_Y - ((i + 1) as f32 * STATS_TEXT_SIZE), Self::standing_color(standing.pid, teams));
+		}
+
+		Self {
+			texts: texts
+		}
+	}
+
+	pub fn draw(&mut self, target: &mut Frame, display: &Display, program: &Program,
+		font: &LoadedFont) -> Result<(), FontError> {
+		for text in &mut self.texts {
+			text.draw(target, display, program, font)?;
+		}
+		Ok(())
+	}
+}
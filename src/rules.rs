@@ -0,0 +1,190 @@
+use serde::{Serialize, Deserialize};
+use crate::zone::ZoneRules;
+use crate::mode::{GameModeKind, HillRules};
+use crate::powerup::PowerUpRules;
+
+#[derive(Serialize, Deserialize, Clone)]
+#[serde(default)]
+pub struct MatchRules {
+	pub mode: GameModeKind,
+	/// Players that need to be ready before warmup begins
+	pub min_players: usize,
+	/// If false, connected players count towards `min_players` without readying up
+	pub require_ready: bool,
+	pub warmup_seconds: usize,
+	pub game_seconds: usize,
+	pub finish_seconds: usize,
+	pub pickup_distance: f32,
+	/// Distance between a player and the first pack in their chain
+	pub starting_follow_distance: f32,
+	/// Extra distance between a player and their first pack for every other pack they own
+	pub follow_distance_incr: f32,
+	/// Distance between consecutive packs in a chain
+	pub chain_spacing: f32,
+	/// If false, a pack can be stolen from any player regardless of pack counts
+	pub steal_requires_bigger_pack: bool,
+	/// Seconds before a player can pick up a pack they dropped on purpose
+	pub drop_pickup_cooldown_seconds: f32,
+	/// Number of teams, players are on their own if less than 2
+	pub team_count: u8,
+	/// Rounds in a best of N match, 1 plays single rounds
+	pub rounds: u8,
+	/// Maps to cycle through between rounds, the current map is kept if empty
+	pub round_maps: Vec<String>,
+	pub zone: ZoneRules,
+	pub hill: HillRules,
+	pub overtime: OvertimeRules,
+	pub steal: StealRules,
+	pub respawn: RespawnRules,
+	pub variants: VariantRules,
+	pub powerups: PowerUpRules,
+	pub howl: HowlRules
+}
+
+#[derive(Serialize, Deserialize, Clone)]
+#[serde(default)]
+pub struct HowlRules {
+	pub enabled: bool,
+	pub cooldown_seconds: f32,
+	/// Enemy packs within this distance of the howling player are affected
+	pub radius: f32,
+	/// Seconds affected packs run from their owners and can be stolen regardless of pack counts
+	pub duration_seconds: f32
+}
+
+impl Default for HowlRules {
+	fn default() -> Self {
+		Self {
+			enabled: true,
+			cooldown_seconds: 20.,
+			radius: 10.,
+			duration_seconds: 3.
+		}
+	}
+}
+
+#[derive(Serialize, Deserialize, Clone)]
+#[serde(default)]
+pub struct VariantRules {
+	/// Chance of a spawned pack being an alpha pack, worth more wolves
+	pub alpha_chance: f32,
+	/// Chance of a spawned pack giving its new owner a speed boost
+	pub speed_chance: f32,
+	/// Chance of a spawned pack being a decoy, which scatters instead of being captured
+	pub decoy_chance: f32,
+	pub speed_multiplier: f32,
+	pub speed_seconds: f32
+}
+
+impl Default for VariantRules {
+	fn default() -> Self {
+		Self {
+			alpha_chance: 0.1,
+			speed_chance: 0.1,
+			decoy_chance: 0.1,
+			speed_multiplier: 1.4,
+			speed_seconds: 6.
+		}
+	}
+}
+
+#[derive(Serialize, Deserialize, Clone)]
+#[serde(default)]
+pub struct RespawnRules {
+	/// If false, only the packs spawned at the start of the match are available
+	pub enabled: bool,
+	/// A wave spawns when fewer neutral packs than this are left
+	pub neutral_threshold: usize,
+	pub wave_size: usize,
+	/// Minimum seconds between waves
+	pub wave_interval_seconds: f32,
+	/// Packs don't spawn closer than this to any player
+	pub min_player_distance: f32,
+	/// Waves stop once this many packs exist in total
+	pub max_packs: usize
+}
+
+impl Default for RespawnRules {
+	fn default() -> Self {
+		Self {
+			enabled: false,
+			neutral_threshold: 3,
+			wave_size: 3,
+			wave_interval_seconds: 20.,
+			min_player_distance: 12.,
+			max_packs: 40
+		}
+	}
+}
+
+#[derive(Serialize, Deserialize, Clone)]
+#[serde(default)]
+pub struct StealRules {
+	/// Seconds a player has to stay within pickup distance of a pack to steal it
+	pub capture_seconds: f32,
+	/// Seconds a pack can't be stolen again after changing owner
+	pub protection_seconds: f32,
+	/// If true, steal progress drains while the owner or a teammate is within pickup distance
+	pub owner_defends: bool
+}
+
+impl Default for StealRules {
+	fn default() -> Self {
+		Self {
+			capture_seconds: 1.5,
+			protection_seconds: 3.,
+			owner_defends: true
+		}
+	}
+}
+
+#[derive(Serialize, Deserialize, Clone)]
+#[serde(default)]
+pub struct OvertimeRules {
+	/// If false, a tie at the end of the game is final
+	pub enabled: bool,
+	/// Overtime ends in a tie after this many seconds
+	pub max_seconds: usize,
+	/// Seconds for the zone to close completely during overtime
+	pub zone_close_seconds: f32
+}
+
+impl Default for OvertimeRules {
+	fn default() -> Self {
+		Self {
+			enabled: true,
+			max_seconds: 60,
+			zone_close_seconds: 30.
+		}
+	}
+}
+
+impl Default for MatchRules {
+	fn default() -> Self {
+		Self {
+			mode: Default::default(),
+			min_players: 2,
+			require_ready: true,
+			warmup_seconds: 15,
+			game_seconds: 120,
+			finish_seconds: 12,
+			pickup_distance: 2.,
+			starting_follow_distance: 2.,
+			follow_distance_incr: 1.,
+			chain_spacing: 2.5,
+			steal_requires_bigger_pack: true,
+			drop_pickup_cooldown_seconds: 5.,
+			team_count: 0,
+			rounds: 1,
+			round_maps: Vec::new(),
+			zone: Default::default(),
+			hill: Default::default(),
+			overtime: Default::default(),
+			steal: Default::default(),
+			respawn: Default::default(),
+			variants: Default::default(),
+			powerups: Default::default(),
+			howl: Default::default()
+		}
+	}
+}
@@ -0,0 +1,116 @@
+use std::collections::HashMap;
+use serde::{Serialize, Deserialize};
+use crate::rules::MatchRules;
+use crate::team::Teams;
+use crate::mode::leading_player;
+use log::info;
+
+#[derive(Serialize, Deserialize, Clone, Copy)]
+pub struct RoundInfo {
+	pub round: u8,
+	pub rounds: u8
+}
+
+impl RoundInfo {
+	pub fn is_series(&self) -> bool {
+		self.rounds > 1
+	}
+}
+
+#[derive(Serialize, Deserialize, Clone)]
+pub struct SeriesStanding {
+	pub round: RoundInfo,
+	pub round_wins: Vec<(u8, u8)>,
+	/// Only set once the match is over
+	pub match_winner: Option<u8>,
+	pub match_over: bool
+}
+
+/// Tracks round wins over a best of N match.
+pub struct Series {
+	round: u8,
+	round_wins: HashMap<u8, u8>,
+	match_over: bool
+}
+
+impl Series {
+	pub fn new() -> Self {
+		Self {
+			round: 1,
+			round_wins: HashMap::new(),
+			match_over: false
+		}
+	}
+
+	pub fn reset(&mut self) {
+		*self = Self::new();
+	}
+
+	pub fn remove_player(&mut self, pid: u8) {
+		self.round_wins.remove(&pid);
+	}
+
+	pub fn round_info(&self, rules: &MatchRules) -> RoundInfo {
+		RoundInfo { round: self.round, rounds: rules.rounds.max(1) }
+	}
+
+	pub fn is_over(&self) -> bool {
+		self.match_over
+	}
+
+	/// Map to switch to for the current round, if the rules rotate maps.
+	/// The first round is played on the map the server is already on.
+	pub fn round_map(&self, rules: &MatchRules) -> Option<String> {
+		if rules.round_maps.is_empty() || self.round < 2 {
+			return None;
+		}
+		Some(rules.round_maps[(self.round as usize - 2) % rules.round_maps.len()].clone())
+	}
+
+	fn wins_as_scores(&self) -> HashMap<u8, u32> {
+		self.round_wins.iter().map(|(pid, wins)| (*pid, *wins as u32)).collect()
+	}
+
+	/// Round wins of a player, or of their whole team in team mode
+	fn side_wins(&self, pid: u8, teams: &Teams) -> u8 {
+		match teams.team_of(pid) {
+			Some(team) => teams.members(team).iter().map(|pid| self.round_wins.get(pid).unwrap_or(&0)).sum(),
+			None => *self.round_wins.get(&pid).unwrap_or(&0)
+		}
+	}
+
+	pub fn record_round(&mut self, winner: Option<u8>, rules: &MatchRules, teams: &Teams) -> SeriesStanding {
+		if let Some(pid) = winner {
+			*self.round_wins.entry(pid).or_insert(0) += 1;
+		}
+		let info = self.round_info(rules);
+		let wins_needed = info.rounds / 2 + 1;
+		self.match_over = info.round >= info.rounds ||
+			self.round_wins.keys().any(|pid| self.side_wins(*pid, teams) >= wins_needed);
+
+		let match_winner = if self.match_over {
+			leading_player(&self.wins_as_scores(), teams)
+		} else {
+			None
+		};
+		if info.is_series() {
+			info!("round {}/{} finished", info.round, info.rounds);
+			if let Some(pid) = match_winner {
+				info!("match finished, player {} wins the series", pid);
+			}
+		}
+
+		let mut round_wins: Vec<(u8, u8)> = self.round_wins.iter().map(|(pid, wins)| (*pid, *wins)).collect();
+		round_wins.sort_by(|a, b| b.1.cmp(&a.1).then(a.0.cmp(&b.0)));
+		SeriesStanding {
+			round: info,
+			round_wins: round_wins,
+			match_winner: match_winner,
+			match_over: self.match_over
+		}
+	}
+
+	pub fn next_round(&mut self) {
+		self.round += 1;
+	}
+}
@@ -2,54 +2,213 @@ use cubik::server::ServerContainer;
 use cubik::player::{Player, PlayerControlType};
 use cubik::map::GameMap;
 use crate::msg::AppMessage;
-use crate::constants::{APP_ID, PORT};
-use crate::minipack::MiniPacks;
-use crate::stage::GameStageManager;
-use std::time::{Duration, Instant};
-use std::thread::sleep;
+use crate::constants::{APP_ID, PORT, DEFAULT_MAP, map_path};
+use crate::minipack::{MiniPacks, PackEvent, PackKind};
+use crate::stage::{GameStageManager, GameStage};
+use crate::console::{ServerConsole, ConsoleCommand};
+use crate::server_settings::ServerSettings;
+use crate::logger::init_logger;
+use crate::metrics::{ServerMetrics, SharedMetrics, start_metrics_server};
+use crate::tick::TickScheduler;
+use crate::anticheat::MovementValidator;
+use crate::nav::NavGrid;
+use crate::boost::SpeedBoost;
+use crate::powerup::{PowerUps, PowerUpEvent, PowerUpKind};
+use log::{info, debug, warn, error};
+use std::time::Instant;
 use std::collections::HashMap;
 
 const MAX_PLAYERS: usize = 6;
 const SPAWN_PREFIX: &str = "misc_player_spawn_";
 
+fn find_player_spawns(map: &GameMap) -> Vec<[f32; 3]> {
+	map.misc_objs.iter().filter_map(|(k, v)| {
+		if k.starts_with(SPAWN_PREFIX) {
+			Some(*v)
+		} else {
+			None
+		}
+	}).collect()
+}
+
+/// Loads a map along with its player spawns, or logs why it can't be played
+fn load_server_map(name: &str) -> Option<(GameMap, Vec<[f32; 3]>)> {
+	let map = match GameMap::load_map(&map_path(name), APP_ID, None, None, true) {
+		Ok(map) => map,
+		Err(e) => {
+			error!("failed to load map {}: {:?}", name, e);
+			return None;
+		}
+	};
+	let spawns = find_player_spawns(&map);
+	if spawns.is_empty() {
+		error!("map {} has no {}* objects, not switching to it", name, SPAWN_PREFIX);
+		return None;
+	}
+	Some((map, spawns))
+}
+
+/// Metrics are only recorded when the endpoint is enabled
+fn broadcast(server_container: &mut ServerContainer<AppMessage>, metrics: &Option<SharedMetrics>, msg: AppMessage) {
+	if let Some(metrics) = metrics {
+		metrics.lock().unwrap().record_sent(&msg, server_container.connections.len());
+	}
+	server_container.broadcast(msg);
+}
+
+/// Tells only the kicked player why. The connection is closed on the next tick,
+/// so the message goes out first.
+fn kick(server_container: &mut ServerContainer<AppMessage>, metrics: &Option<SharedMetrics>, kicked_pids: &mut Vec<u8>,
+	pid: u8, reason: String) {
+	let msg = AppMessage::Kicked { player_id: pid, reason: reason };
+	if let Some(metrics) = metrics {
+		metrics.lock().unwrap().record_sent(&msg, 1);
+	}
+	if let Err(e) = server_container.send_to(pid, msg) {
+		warn!("failed to send kick reason to player {}: {:?}", pid, e);
+	}
+	kicked_pids.push(pid);
+}
+
 pub fn start_server() {
+	let settings = ServerSettings::load().unwrap();
+	init_logger(&settings.log).unwrap();
+
 	let mut server_container: ServerContainer<AppMessage> = ServerContainer::new(PORT, MAX_PLAYERS).unwrap();
 
-	println!("server listening on port {}", PORT);
+	info!("server listening on port {}", PORT);
+
+	let metrics = settings.metrics_address.as_ref().map(|address| {
+		let metrics = ServerMetrics::shared();
+		start_metrics_server(address, metrics.clone()).unwrap();
+		metrics
+	});
+
 	let mut last_status_update = Instant::now();
 	let mut player_map: HashMap<u8, Player> = HashMap::new();
 
-	let map = GameMap::load_map("models/map3", APP_ID, None, None, true).unwrap();
-	let player_spawns: Vec<[f32; 3]> = map.misc_objs.iter().filter_map(|(k, v)| {
-		if k.starts_with(SPAWN_PREFIX) {
-			Some(*v)
-		} else {
-			None
-		}
-	}).collect();
+	let mut map_name = DEFAULT_MAP.to_string();
+	let (mut map, mut player_spawns) = load_server_map(&map_name).expect("failed to load the default map");
+	let mut nav_grid = NavGrid::build(&map, MiniPacks::spawn_height(&map));
 	let mut pid_to_spawn_map: HashMap<u8, [f32; 3]> = HashMap::new();
 	let mut packs = MiniPacks::new();
 	let mut player_pack_counts: HashMap<u8, usize> = HashMap::new();
+	let mut speed_boosts: HashMap<u8, SpeedBoost> = HashMap::new();
+	let mut powerups = PowerUps::new();
+	let mut howl_cooldowns: HashMap<u8, f32> = HashMap::new();
 
-	let mut last_time = Instant::now();
+	let mut tick_scheduler = TickScheduler::new(settings.tick_rate, settings.max_catch_up_ticks);
+	let dt = tick_scheduler.dt();
 
-	let mut game_stage_manager = GameStageManager::new();
+	let mut movement_validator = MovementValidator::new(settings.anticheat.clone());
+	let mut kicked_pids: Vec<u8> = Vec::new();
+
+	let mut game_stage_manager = GameStageManager::new(settings.rules.clone());
+
+	let console = ServerConsole::start();
 
 	loop {
 		server_container.update();
+		for pid in kicked_pids.drain(..) {
+			server_container.connections.remove(&pid);
+		}
+		movement_validator.tick(dt);
+
+		for cmd in console.commands() {
+			match cmd {
+				ConsoleCommand::Status => {
+					println!("stage: {:?} map: {} players: {}", game_stage_manager.current_stage, map_name, player_map.len());
+					for (pid, conn) in &server_container.connections {
+						println!("pid: {} name: {} packs: {}", pid, conn.name.as_ref().unwrap_or(&"".to_string()),
+							player_pack_counts.get(pid).unwrap_or(&0));
+					}
+				},
+				ConsoleCommand::Kick(pid) => {
+					if server_container.connections.contains_key(&pid) {
+						kick(&mut server_container, &metrics, &mut kicked_pids, pid, "Kicked by the server".to_string());
+						info!("kicked player {} from console", pid);
+					} else {
+						println!("no player with pid {}", pid);
+					}
+				},
+				ConsoleCommand::Say(text) => {
+					broadcast(&mut server_container, &metrics, AppMessage::ServerSay(text));
+				},
+				ConsoleCommand::Map(name) => {
+					if let Some((new_map, new_spawns)) = load_server_map(&name) {
+						map = new_map;
+						map_name = name;
+						player_spawns = new_spawns;
+						nav_grid = NavGrid::build(&map, MiniPacks::spawn_height(&map));
+						pid_to_spawn_map.clear();
+						player_map.clear();
+						movement_validator.reset_all();
+						broadcast(&mut server_container, &metrics, AppMessage::MapChange(map_name.clone()));
+						if let Some(msg) = game_stage_manager.server_force_stage(GameStage::Warmup, &map, &mut player_map,
+							&mut packs, &mut player_pack_counts) {
+							broadcast(&mut server_container, &metrics, msg);
+						}
+						info!("changed map to {}", map_name);
+					}
+				},
+				ConsoleCommand::Restart => {
+					if let Some(msg) = game_stage_manager.server_force_stage(GameStage::Warmup, &map, &mut player_map,
+						&mut packs, &mut player_pack_counts) {
+						broadcast(&mut server_container, &metrics, msg);
+					}
+				},
+				ConsoleCommand::Stage(stage) => {
+					if let Some(msg) = game_stage_manager.server_force_stage(stage, &map, &mut player_map,
+						&mut packs, &mut player_pack_counts) {
+						broadcast(&mut server_container, &metrics, msg);
+					}
+				},
+				ConsoleCommand::Packs => {
+					for (i, pack) in packs.packs.iter().enumerate() {
+						println!("pack: {} position: ({:.2}, {:.2}) owner: {} kind: {:?}", i, pack.position.0, pack.position.1,
+							pack.owner.map(|v| v.to_string()).unwrap_or("none".to_string()), pack.kind);
+					}
+				}
+			};
+		}
+		if game_stage_manager.take_respawned() {
+			movement_validator.reset_all();
+		}
 
 		let current_pids = server_container.pids();
+		let mut disconnected = false;
 		player_map.retain(|&k, _| {
 			if !current_pids.contains(&k) {
+				info!("player {} disconnected", k);
+				disconnected = true;
+				movement_validator.remove(k);
+				game_stage_manager.remove_player(k);
 				player_pack_counts.remove(&k);
+				speed_boosts.remove(&k);
+				powerups.remove_player(k);
+				howl_cooldowns.remove(&k);
 				pid_to_spawn_map.remove(&k);
 				false
 			} else {
 				true
 			}
 		});
+		if disconnected && game_stage_manager.teams.enabled() {
+			broadcast(&mut server_container, &metrics, AppMessage::TeamsChange(game_stage_manager.teams.clone()));
+		}
 
+		let mut pack_events = Vec::new();
 		for pid in current_pids {
+			if let Some(name) = server_container.connections.get(&pid).and_then(|c| c.name.as_ref()) {
+				game_stage_manager.set_player_name(pid, name);
+			}
+			if !player_map.contains_key(&pid) {
+				info!("player {} connected", pid);
+				broadcast(&mut server_container, &metrics, AppMessage::MapChange(map_name.clone()));
+				broadcast(&mut server_container, &metrics, AppMessage::RulesChange(game_stage_manager.rules.clone()));
+				game_stage_manager.teams.assign(pid);
+				broadcast(&mut server_container, &metrics, AppMessage::TeamsChange(game_stage_manager.teams.clone()));
+			}
 			let player = player_map.entry(pid)
 				.or_insert_with(|| {
 					let spawn: [f32; 3] = *player_spawns.iter().find(|s| {
@@ -59,50 +218,186 @@ pub fn start_server() {
 						} else {
 							false
 						}
-					}).unwrap_or(player_spawns.get(0).unwrap());
+					}).unwrap_or(&player_spawns[0]);
 					let mut player = Player::new(spawn, PlayerControlType::MultiplayerServer,
 						[-0.28, 0.275, 0.0], [0.44, 0.275, 0.08]);
 					player.move_rate = 2.56;
 					player
 				});
 			if let Ok(msgs) = server_container.get_msgs(pid) {
+				let previous_position = player.camera.position;
+				let mut moved = false;
 				for msg in msgs {
-					if let AppMessage::PlayerChange { msg, .. } = msg {
-						player.update(0., Some(&map.quadoctree.as_ref().unwrap()), None, Some(msg));
+					if let Some(metrics) = metrics.as_ref() {
+						metrics.lock().unwrap().record_received(&msg);
 					}
+					match msg {
+						AppMessage::PlayerChange { msg, .. } => {
+							player.update(0., Some(&map.quadoctree.as_ref().unwrap()), None, Some(msg));
+							moved = true;
+						},
+						AppMessage::ReadyToggle => game_stage_manager.toggle_ready(pid),
+						AppMessage::Howl => {
+							let in_game = match game_stage_manager.current_stage {
+								GameStage::InProgress | GameStage::Overtime => true,
+								_ => false
+							};
+							let rules = &game_stage_manager.rules.howl;
+							if rules.enabled && in_game && !game_stage_manager.is_eliminated(pid) && !howl_cooldowns.contains_key(&pid) {
+								let position = (player.camera.position[0], player.camera.position[2]);
+								let scared = packs.howl(pid, position, rules, &game_stage_manager.teams);
+								howl_cooldowns.insert(pid, rules.cooldown_seconds);
+								info!("player {} howled, {} packs scared", pid, scared);
+								broadcast(&mut server_container, &metrics, AppMessage::Howled(pid));
+							}
+						},
+						AppMessage::DropPack => {
+							let in_game = match game_stage_manager.current_stage {
+								GameStage::InProgress | GameStage::Overtime => true,
+								_ => false
+							};
+							// dropping the last of the player's wolves would eliminate them
+							let wolves = *packs.wolf_counts().get(&pid).unwrap_or(&0);
+							let dropped = packs.last_pack_wolves(pid);
+							if in_game && dropped > 0 && (wolves > dropped || !game_stage_manager.mode().uses_elimination()) {
+								let position = (player.camera.position[0], player.camera.position[2]);
+								if packs.drop_pack(pid, position, game_stage_manager.rules.drop_pickup_cooldown_seconds) {
+									let count = player_pack_counts.entry(pid).or_insert(0);
+									*count = count.saturating_sub(1);
+									info!("player {} dropped a pack", pid);
+								}
+							}
+						},
+						_ => ()
+					};
 				}
-				if let Some(msg) = player.update(last_time.elapsed().as_secs_f32(), Some(&map.quadoctree.as_ref().unwrap()), None, None) {
-					server_container.broadcast(AppMessage::PlayerChange {
+				if moved {
+					if let Some(reason) = movement_validator.validate(pid, previous_position, player,
+						map.quadoctree.as_ref().unwrap()) {
+						warn!("kicked player {}: {}", pid, reason);
+						kick(&mut server_container, &metrics, &mut kicked_pids, pid, format!("Invalid movement: {}", reason));
+						continue;
+					}
+				}
+				if let Some(msg) = player.update(dt, Some(&map.quadoctree.as_ref().unwrap()), None, None) {
+					broadcast(&mut server_container, &metrics, AppMessage::PlayerChange {
 						msg: msg,
 						player_id: pid
 					});
 				}
+				if game_stage_manager.is_eliminated(pid) { continue; }
+				let pickup_distance = if powerups.is_active(pid, PowerUpKind::Magnet) {
+					game_stage_manager.rules.powerups.magnet_pickup_distance
+				} else {
+					game_stage_manager.rules.pickup_distance
+				};
 				for pack in &mut packs.packs {
-					pack.player_server_update(pid, &player, &mut player_pack_counts);
+					if let Some(event) = pack.player_server_update(pid, &player, &mut player_pack_counts,
+						pickup_distance, game_stage_manager.mode(), &nav_grid) {
+						pack_events.push(event);
+					}
+				}
+			}
+		}
+
+		let active_players: Vec<(u8, [f32; 3])> = player_map.iter()
+			.filter(|(pid, _)| !game_stage_manager.is_eliminated(**pid))
+			.map(|(pid, player)| (*pid, player.camera.position))
+			.collect();
+		let player_positions: Vec<[f32; 3]> = active_players.iter().map(|(_, position)| *position).collect();
+		for pack in &mut packs.packs {
+			pack.server_update(dt, &nav_grid, &player_positions);
+		}
+		pack_events.extend(packs.steal_update(dt, &active_players, &mut player_pack_counts, &game_stage_manager.rules,
+			&game_stage_manager.teams, game_stage_manager.mode(), &powerups.active_pids(PowerUpKind::StealShield)));
+		for event in &pack_events {
+			game_stage_manager.record_pack_event(event);
+			if let PackEvent::Captured { pid, kind: PackKind::Speed } | PackEvent::Stolen { pid, kind: PackKind::Speed, .. } = event {
+				if let Some(player) = player_map.get_mut(pid) {
+					let variants = &game_stage_manager.rules.variants;
+					speed_boosts.entry(*pid).or_insert_with(SpeedBoost::new).start(player, variants.speed_multiplier, variants.speed_seconds);
 				}
 			}
 		}
+		for (pid, boost) in speed_boosts.iter_mut() {
+			if let Some(player) = player_map.get_mut(pid) {
+				boost.update(player, dt);
+			}
+		}
+		packs.follow_update(dt, &player_map, &game_stage_manager.rules, &nav_grid);
+		for cooldown in howl_cooldowns.values_mut() {
+			*cooldown -= dt;
+		}
+		howl_cooldowns.retain(|_, cooldown| *cooldown > 0.);
+		if let GameStage::InProgress | GameStage::Overtime = game_stage_manager.current_stage {
+			let rules = &game_stage_manager.rules;
+			let spawned = packs.respawn_update(dt, &map, &nav_grid, &player_positions, &rules.respawn, &rules.variants);
+			if spawned > 0 {
+				broadcast(&mut server_container, &metrics, AppMessage::PacksRespawned(spawned.min(u16::MAX as usize) as u16));
+			}
 
-		if let Some(msg) = game_stage_manager.server_update(&map, &mut player_map,
+			if rules.powerups.enabled {
+				if !powerups.is_spawned() {
+					powerups.spawn(&map);
+				}
+				for event in powerups.server_update(dt, &active_players, &rules.powerups) {
+					if let PowerUpEvent::PickedUp { pid, kind: PowerUpKind::SpeedBurst, .. } = event {
+						if let Some(player) = player_map.get_mut(&pid) {
+							speed_boosts.entry(pid).or_insert_with(SpeedBoost::new)
+								.start(player, rules.powerups.speed_multiplier, rules.powerups.speed_seconds);
+						}
+					}
+					broadcast(&mut server_container, &metrics, AppMessage::PowerUp(event));
+				}
+				if let Some(snapshot) = powerups.server_snapshot(dt) {
+					broadcast(&mut server_container, &metrics, AppMessage::PowerUpSnapshot(snapshot));
+				}
+			}
+		} else if powerups.is_spawned() {
+			powerups.clear();
+		}
+
+		if let Some(msg) = game_stage_manager.server_update(&map, &nav_grid, &mut player_map,
 			&mut packs, &mut player_pack_counts) {
-			server_container.broadcast(msg);
+			broadcast(&mut server_container, &metrics, msg);
+		}
+		if game_stage_manager.take_respawned() {
+			movement_validator.reset_all();
 		}
 
-		if let Some(msg) = packs.server_update_msg(last_time.elapsed().as_secs_f32()) {
-			server_container.broadcast(msg);
+		if let Some(name) = game_stage_manager.take_map_change().filter(|name| *name != map_name) {
+			if let Some((new_map, new_spawns)) = load_server_map(&name) {
+				map = new_map;
+				map_name = name;
+				player_spawns = new_spawns;
+				nav_grid = NavGrid::build(&map, MiniPacks::spawn_height(&map));
+				pid_to_spawn_map.clear();
+				player_map.clear();
+				movement_validator.reset_all();
+				broadcast(&mut server_container, &metrics, AppMessage::MapChange(map_name.clone()));
+				info!("changed map to {} for the next round", map_name);
+			}
+		}
+
+		if let Some(msg) = packs.server_update_msg(dt) {
+			broadcast(&mut server_container, &metrics, msg);
 		}
 
 		if last_status_update.elapsed().as_secs_f32() > 5. {
 			last_status_update = Instant::now();
-			println!("peer status update:");
+			debug!("peer status update:");
 			for (pid, conn) in &server_container.connections {
-				println!("pid: {} name: {}", pid, conn.name.as_ref().unwrap_or(&"".to_string()));
+				debug!("pid: {} name: {}", pid, conn.name.as_ref().unwrap_or(&"".to_string()));
 			}
-			println!("");
 		}
 
-		last_time = Instant::now();
+		if let Some(metrics) = metrics.as_ref() {
+			let mut metrics = metrics.lock().unwrap();
+			metrics.player_count = player_map.len();
+			metrics.stage = format!("{:?}", game_stage_manager.current_stage);
+			metrics.record_tick(tick_scheduler.tick_elapsed().as_secs_f64());
+		}
 
-		sleep(Duration::from_millis(17));
+		tick_scheduler.wait();
 	}
 }
@@ -0,0 +1,35 @@
+use crate::settings::{SettingsError, home_path, load_toml};
+use crate::logger::LogSettings;
+use crate::anticheat::AntiCheatSettings;
+use crate::rules::MatchRules;
+use serde::{Serialize, Deserialize};
+
+#[derive(Serialize, Deserialize)]
+#[serde(default)]
+pub struct ServerSettings {
+	pub tick_rate: u32,
+	pub max_catch_up_ticks: u32,
+	pub metrics_address: Option<String>,
+	pub rules: MatchRules,
+	pub anticheat: AntiCheatSettings,
+	pub log: LogSettings
+}
+
+impl Default for ServerSettings {
+	fn default() -> Self {
+		Self {
+			tick_rate: 60,
+			max_catch_up_ticks: 5,
+			metrics_address: None,
+			rules: Default::default(),
+			anticheat: Default::default(),
+			log: Default::default()
+		}
+	}
+}
+
+impl ServerSettings {
+	pub fn load() -> Result<Self, SettingsError> {
+		load_toml(home_path(".wolfpack_server")?)
+	}
+}
@@ -4,6 +4,8 @@ use std::io::{self, Read, Write};
 use std::fs::File;
 use std::path::PathBuf;
 use serde::{Serialize, Deserialize};
+use serde::de::DeserializeOwned;
+use crate::logger::LogSettings;
 
 #[derive(From, Error, Display, Debug)]
 pub enum SettingsError {
@@ -19,46 +21,63 @@ pub const RESOLUTION_OPTIONS: [[usize; 2]; 3] = [
 	[720, 480]
 ];
 
-#[derive(Serialize, Deserialize, Copy, Clone)]
+#[derive(Serialize, Deserialize, Clone)]
+#[serde(default)]
 pub struct Settings {
 	pub resolution: [usize; 2],
-	pub windowed: bool
+	pub windowed: bool,
+	pub log: LogSettings
 }
 
 impl Default for Settings {
 	fn default() -> Self {
 		Self {
 			resolution: [1280, 720],
-			windowed: false
+			windowed: false,
+			log: LogSettings {
+				file: home_path(".wolfpack.log").ok(),
+				..Default::default()
+			}
 		}
 	}
 }
 
+pub fn home_path(name: &str) -> Result<PathBuf, SettingsError> {
+	let mut home = home_dir().ok_or(SettingsError::NoHomeDir)?;
+	home.push(name);
+	Ok(home)
+}
+
+pub fn load_toml<T: Serialize + DeserializeOwned + Default>(path: PathBuf) -> Result<T, SettingsError> {
+	if !path.exists() {
+		let default_value: T = Default::default();
+		save_toml(&default_value, path)?;
+		return Ok(default_value);
+	}
+
+	let mut file = File::open(path)?;
+	let mut contents = String::new();
+	file.read_to_string(&mut contents)?;
+	Ok(toml::from_str(&contents)?)
+}
+
+pub fn save_toml<T: Serialize>(value: &T, path: PathBuf) -> Result<(), SettingsError> {
+	let serialized = toml::to_string(value)?;
+	let mut file = File::create(path)?;
+	file.write_all(serialized.as_bytes())?;
+	Ok(())
+}
+
 impl Settings {
 	fn settings_path() -> Result<PathBuf, SettingsError> {
-		let mut home = home_dir().ok_or(SettingsError::NoHomeDir)?;
-		home.push(".wolfpack");
-		Ok(home)
+		home_path(".wolfpack")
 	}
 
 	pub fn load() -> Result<Self, SettingsError> {
-		let settings_path = Self::settings_path()?;
-		if !settings_path.exists() {
-			let default_settings: Settings = Default::default();
-			default_settings.save()?;
-			return Ok(default_settings);
-		}
-
-		let mut file = File::open(settings_path)?;
-		let mut contents = String::new();
-		file.read_to_string(&mut contents)?;
-		Ok(toml::from_str(&contents)?)
+		load_toml(Self::settings_path()?)
 	}
 	
 	pub fn save(&self) -> Result<(), SettingsError> {
-		let serialized = toml::to_string(self)?;
-		let mut file = File::create(Self::settings_path()?)?;
-		file.write_all(serialized.as_bytes())?;
-		Ok(())
+		save_toml(self, Self::settings_path()?)
 	}
 }
@@ -1,71 +1,464 @@
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet};
 use cubik::map::GameMap;
+use cubik::client::PeerMeta;
 use cubik::fonts::{FontText, LoadedFont, FontError, TextAlign};
 use cubik::player::Player;
-use crate::minipack::MiniPacks;
+use crate::minipack::{MiniPacks, PackEvent};
 use crate::msg::AppMessage;
+use crate::rules::MatchRules;
+use crate::results::ResultsScreen;
+use crate::zone::{ZoneState, SafeZone};
+use crate::nav::NavGrid;
+use crate::team::Teams;
+use crate::mode::{GameMode, ModeContext, Hill, leading_player, tied_leaders};
+use crate::series::{Series, RoundInfo, SeriesStanding};
 use std::time::Instant;
 use cubik::glium::{Display, Program, Frame};
 use serde::{Serialize, Deserialize};
-
-const WARMUP_SECONDS: usize = 15;
-const GAME_SECONDS: usize = 120;
-const FINISH_SECONDS: usize = 12;
-const EARLY_FINISH_SECONDS: usize = 120;
+use log::info;
 
 const WARMUP_TEXT_SIZE: f32 = 0.11;
 const MAIN_TEXT_SIZE: f32 = 0.15;
+const LOBBY_TEXT_SIZE: f32 = 0.08;
+const ROUND_TEXT_SIZE: f32 = 0.06;
 
 pub struct GameStageManager {
 	pub current_stage: GameStage,
+	pub rules: MatchRules,
+	pub teams: Teams,
+	mode: Box<dyn GameMode>,
+	pub scores: HashMap<u8, u32>,
+	pub hill: Option<Hill>,
+	series: Series,
+	overtime_tied: Vec<u8>,
+	overtime_winner: Option<u8>,
+	overtime_zone: Option<SafeZone>,
+	pending_map: Option<String>,
+	players_respawned: bool,
 	stage_start_time: Instant,
 	last_update_time: Instant,
-	text: Option<FontText>
+	ready_pids: HashSet<u8>,
+	forced_start: bool,
+	had_packs: HashSet<u8>,
+	pub eliminated: HashSet<u8>,
+	player_names: HashMap<u8, String>,
+	player_stats: HashMap<u8, PlayerMatchStats>,
+	zone_state: Option<ZoneState>,
+	zone_outside_times: HashMap<u8, f32>,
+	pub zone: Option<SafeZone>,
+	text: Option<FontText>,
+	detail_text: Option<FontText>,
+	round_text: Option<FontText>,
+	results: Option<ResultsScreen>
+}
+
+#[derive(Default)]
+struct PlayerMatchStats {
+	captures: u16,
+	steals: u16
+}
+
+#[derive(Serialize, Deserialize, Clone)]
+pub struct PlayerStanding {
+	pub pid: u8,
+	pub name: String,
+	pub wolves: usize,
+	pub score: u32,
+	pub captures: u16,
+	pub steals: u16,
+	pub eliminated: bool
 }
 
 #[derive(Debug)]
 pub enum GameStage {
 	Standby,
+	Lobby,
 	Warmup,
 	InProgress,
+	Overtime,
 	Finished
 }
 
 #[derive(Serialize, Deserialize)]
 pub enum GameStageUpdate {
-	Warmup { time_remaining: u8 },
-	InProgress { time_remaining: u16 },
-	Finished
+	Lobby { ready_pids: Vec<u8>, min_players: u8 },
+	Warmup { time_remaining: u16, round: RoundInfo },
+	InProgress { time_remaining: u16, eliminated: Vec<u8>, zone: Option<SafeZone>, scores: Vec<(u8, u32)>,
+		hill: Option<Hill>, round: RoundInfo },
+	Overtime { time_remaining: u16, tied: Vec<u8>, eliminated: Vec<u8>, zone: Option<SafeZone>, scores: Vec<(u8, u32)>,
+		hill: Option<Hill>, round: RoundInfo },
+	Finished { winner: Option<u8>, winning_team: Option<u8>, standings: Vec<PlayerStanding>,
+		series: Option<SeriesStanding> }
 }
 
 impl GameStageManager {
-	pub fn new() -> Self {
+	pub fn new(rules: MatchRules) -> Self {
 		Self {
 			current_stage: GameStage::Standby,
+			teams: Teams::new(rules.team_count),
+			mode: rules.mode.create(),
+			scores: HashMap::new(),
+			hill: None,
+			series: Series::new(),
+			overtime_tied: Vec::new(),
+			overtime_winner: None,
+			overtime_zone: None,
+			pending_map: None,
+			players_respawned: false,
+			rules: rules,
 			stage_start_time: Instant::now(),
 			last_update_time: Instant::now(),
-			text: None
+			ready_pids: HashSet::new(),
+			forced_start: false,
+			had_packs: HashSet::new(),
+			eliminated: HashSet::new(),
+			player_names: HashMap::new(),
+			player_stats: HashMap::new(),
+			zone_state: None,
+			zone_outside_times: HashMap::new(),
+			zone: None,
+			text: None,
+			detail_text: None,
+			round_text: None,
+			results: None
+		}
+	}
+
+	pub fn toggle_ready(&mut self, pid: u8) {
+		if !self.ready_pids.remove(&pid) {
+			self.ready_pids.insert(pid);
+		}
+	}
+
+	pub fn remove_player(&mut self, pid: u8) {
+		self.ready_pids.remove(&pid);
+		self.had_packs.remove(&pid);
+		self.eliminated.remove(&pid);
+		self.player_names.remove(&pid);
+		self.player_stats.remove(&pid);
+		self.series.remove_player(pid);
+		self.teams.remove(pid);
+	}
+
+	pub fn set_player_name(&mut self, pid: u8, name: &str) {
+		if self.player_names.get(&pid).map(|v| v != name).unwrap_or(true) {
+			self.player_names.insert(pid, name.to_string());
+		}
+	}
+
+	pub fn record_pack_event(&mut self, event: &PackEvent) {
+		let pid = match event {
+			PackEvent::Captured { pid, .. } => {
+				self.player_stats.entry(*pid).or_default().captures += 1;
+				*pid
+			},
+			PackEvent::Stolen { pid, .. } => {
+				self.player_stats.entry(*pid).or_default().steals += 1;
+				*pid
+			}
+		};
+		// in sudden death, the first capture or steal by one of the tied players wins
+		if let GameStage::Overtime = self.current_stage {
+			if self.overtime_winner.is_none() && self.is_overtime_side(pid) {
+				self.overtime_winner = Some(pid);
+			}
+		}
+	}
+
+	/// Whether the player is one of the tied players in overtime, or on their team
+	fn is_overtime_side(&self, pid: u8) -> bool {
+		self.overtime_tied.iter().any(|tied| self.teams.are_teammates(pid, *tied))
+	}
+
+	pub fn mode(&self) -> &dyn GameMode {
+		self.mode.as_ref()
+	}
+
+	/// Map the server should switch to before the next round starts
+	pub fn take_map_change(&mut self) -> Option<String> {
+		self.pending_map.take()
+	}
+
+	/// Whether players were moved back to spawn since the last call
+	pub fn take_respawned(&mut self) -> bool {
+		std::mem::replace(&mut self.players_respawned, false)
+	}
+
+	fn standings(&self, winner: Option<u8>, player_map: &HashMap<u8, Player>, packs: &MiniPacks) -> Vec<PlayerStanding> {
+		let wolf_counts = packs.wolf_counts();
+		let scores = self.mode.scores(&wolf_counts);
+		let mut standings: Vec<PlayerStanding> = player_map.keys().map(|pid| {
+			let stats = self.player_stats.get(pid);
+			PlayerStanding {
+				pid: *pid,
+				name: self.player_names.get(pid).cloned().unwrap_or_default(),
+				wolves: *wolf_counts.get(pid).unwrap_or(&0),
+				score: *scores.get(pid).unwrap_or(&0),
+				captures: stats.map(|s| s.captures).unwrap_or(0),
+				steals: stats.map(|s| s.steals).unwrap_or(0),
+				eliminated: self.eliminated.contains(pid)
+			}
+		}).collect();
+		standings.sort_by(|a, b| {
+			(Some(b.pid) == winner).cmp(&(Some(a.pid) == winner))
+				.then(b.score.cmp(&a.score))
+				.then(b.wolves.cmp(&a.wolves))
+				.then(a.eliminated.cmp(&b.eliminated))
+				.then((b.captures + b.steals).cmp(&(a.captures + a.steals)))
+		});
+		standings
+	}
+
+	pub fn is_eliminated(&self, pid: u8) -> bool {
+		self.eliminated.contains(&pid)
+	}
+
+	/// With teams, players are only eliminated once their whole team is out of packs
+	fn update_eliminations(&mut self, player_pack_counts: &HashMap<u8, usize>) {
+		for pid in player_pack_counts.keys() {
+			if self.teams.side_total(*pid, player_pack_counts) > 0 {
+				self.had_packs.insert(*pid);
+			} else if self.had_packs.contains(pid) && self.eliminated.insert(*pid) {
+				info!("player {} was eliminated", pid);
+			}
 		}
 	}
 
+	fn eliminated_list(&self) -> Vec<u8> {
+		let mut eliminated: Vec<u8> = self.eliminated.iter().cloned().collect();
+		eliminated.sort();
+		eliminated
+	}
+
+	/// Takes packs away from players who stay outside of the zone for too long.
+	/// Meant to be called once a second.
+	fn server_zone_update(&mut self, zone: Option<SafeZone>, player_map: &HashMap<u8, Player>, packs: &mut MiniPacks,
+		nav: &NavGrid, player_pack_counts: &mut HashMap<u8, usize>) {
+		self.zone = zone;
+		let zone = match zone {
+			Some(zone) => zone,
+			None => return
+		};
+
+		for (pid, player) in player_map {
+			if self.eliminated.contains(pid) || zone.contains(&player.camera.position) {
+				self.zone_outside_times.remove(pid);
+				continue;
+			}
+			let outside_time = self.zone_outside_times.entry(*pid).or_insert(0.);
+			*outside_time += 1.;
+			if *outside_time < self.rules.zone.pack_loss_seconds { continue; }
+			*outside_time = 0.;
+			if packs.release_pack(*pid, self.rules.zone.scatter_lost_packs, nav) {
+				if let Some(count) = player_pack_counts.get_mut(pid) {
+					*count = count.saturating_sub(1);
+				}
+				info!("player {} lost a pack outside the zone", pid);
+			}
+		}
+	}
+
+	/// Zone that closes completely over the overtime, starting from the zone at the end of the game.
+	fn overtime_zone(&self, elapsed_secs: usize) -> Option<SafeZone> {
+		let start = self.overtime_zone?;
+		let close_seconds = self.rules.overtime.zone_close_seconds.max(1.);
+		let fraction = (1. - elapsed_secs as f32 / close_seconds).max(0.);
+		Some(SafeZone {
+			center: start.center,
+			radius: start.radius * fraction,
+			target_radius: 0.,
+			seconds_to_shrink: 0,
+			shrinking: fraction > 0.
+		})
+	}
+
+	fn score_list(&self, packs: &MiniPacks) -> Vec<(u8, u32)> {
+		let mut scores: Vec<(u8, u32)> = self.mode.scores(&packs.wolf_counts()).into_iter().collect();
+		scores.sort();
+		scores
+	}
+
+	fn ready_count(&self, player_map: &HashMap<u8, Player>) -> usize {
+		if self.rules.require_ready {
+			self.ready_pids.len()
+		} else {
+			player_map.len()
+		}
+	}
+
+	fn server_lobby(&mut self, player_map: &mut HashMap<u8, Player>, packs: &mut MiniPacks,
+		player_pack_counts: &mut HashMap<u8, usize>) -> Option<GameStageUpdate> {
+		if player_map.is_empty() || self.ready_count(player_map) >= self.rules.min_players {
+			return self.server_warmup(player_map, packs, player_pack_counts);
+		}
+		let in_lobby = match self.current_stage {
+			GameStage::Lobby => true,
+			_ => false
+		};
+		if !in_lobby {
+			self.series.reset();
+			self.current_stage = GameStage::Lobby;
+			self.stage_start_time = Instant::now();
+			packs.packs.clear();
+			player_pack_counts.clear();
+			info!("waiting in lobby for {} ready players", self.rules.min_players);
+		}
+		let mut ready_pids: Vec<u8> = self.ready_pids.iter().cloned().collect();
+		ready_pids.sort();
+		Some(GameStageUpdate::Lobby { ready_pids: ready_pids, min_players: self.rules.min_players as u8 })
+	}
+
 	fn server_start_game(&mut self, map: &GameMap, player_map: &mut HashMap<u8, Player>, packs: &mut MiniPacks,
 		player_pack_counts: &mut HashMap<u8, usize>) -> Option<GameStageUpdate> {
 		packs.packs.clear();
 		player_pack_counts.clear();
 
 		packs.spawn(map);
+		packs.choose_kinds(&self.rules.variants);
 		for player in player_map.values_mut() {
 			player.respawn();
 		}
+		self.players_respawned = true;
+		self.had_packs.clear();
+		self.eliminated.clear();
+		self.player_stats.clear();
+		self.zone_outside_times.clear();
+		self.zone_state = if self.rules.zone.enabled {
+			Some(ZoneState::for_map(map))
+		} else {
+			None
+		};
+		self.zone = self.zone_state.as_ref().map(|z| z.at(0., &self.rules.zone));
+
+		self.mode = self.rules.mode.create();
+		self.mode.start(&ModeContext {
+			map: map,
+			player_map: player_map,
+			packs: packs,
+			player_pack_counts: player_pack_counts,
+			rules: &self.rules,
+			teams: &self.teams,
+			eliminated: &self.eliminated
+		});
 
 		self.current_stage = GameStage::InProgress;
 		self.stage_start_time = Instant::now();
-		Some(GameStageUpdate::InProgress { time_remaining: GAME_SECONDS as u16 })
+		info!("{} game started with {} players", self.mode.kind().name(), player_map.len());
+		Some(GameStageUpdate::InProgress {
+			time_remaining: self.rules.game_seconds as u16,
+			eliminated: Vec::new(),
+			zone: self.zone,
+			scores: self.score_list(packs),
+			hill: self.mode.hill(),
+			round: self.series.round_info(&self.rules)
+		})
+	}
+
+	fn server_warmup(&mut self, player_map: &mut HashMap<u8, Player>, packs: &mut MiniPacks,
+		player_pack_counts: &mut HashMap<u8, usize>) -> Option<GameStageUpdate> {
+		self.stage_start_time = Instant::now();
+		packs.packs.clear();
+		player_pack_counts.clear();
+		if !player_map.is_empty() {
+			self.current_stage = GameStage::Warmup;
+			let round = self.series.round_info(&self.rules);
+			info!("warmup for round {}/{} started with {} players", round.round, round.rounds, player_map.len());
+			Some(GameStageUpdate::Warmup { time_remaining: self.rules.warmup_seconds as u16, round: round })
+		} else {
+			self.current_stage = GameStage::Standby;
+			info!("no players, standing by");
+			None
+		}
+	}
+
+	fn server_overtime(&mut self, tied: Vec<u8>, packs: &MiniPacks) -> Option<GameStageUpdate> {
+		self.current_stage = GameStage::Overtime;
+		self.stage_start_time = Instant::now();
+		self.overtime_winner = None;
+		self.overtime_zone = self.zone;
+		info!("players {:?} are tied, starting overtime", tied);
+		self.overtime_tied = tied.clone();
+		Some(GameStageUpdate::Overtime {
+			time_remaining: self.rules.overtime.max_seconds as u16,
+			tied: tied,
+			eliminated: self.eliminated_list(),
+			zone: self.zone,
+			scores: self.score_list(packs),
+			hill: self.mode.hill(),
+			round: self.series.round_info(&self.rules)
+		})
+	}
+
+	fn server_finish_game(&mut self, winner: Option<u8>, player_map: &HashMap<u8, Player>,
+		packs: &MiniPacks) -> Option<GameStageUpdate> {
+		self.zone_state = None;
+		self.zone = None;
+		self.overtime_tied.clear();
+		self.overtime_winner = None;
+		self.overtime_zone = None;
+		self.current_stage = GameStage::Finished;
+		self.stage_start_time = Instant::now();
+		self.forced_start = false;
+		match winner {
+			Some(pid) => info!("game finished, player {} wins", pid),
+			None => info!("game finished without a winner")
+		};
+		let series = self.series.record_round(winner, &self.rules, &self.teams);
+		Some(GameStageUpdate::Finished {
+			winner: winner,
+			winning_team: winner.and_then(|pid| self.teams.team_of(pid)),
+			standings: self.standings(winner, player_map, packs),
+			series: if series.round.is_series() { Some(series) } else { None }
+		})
 	}
 
-	pub fn server_update(&mut self, map: &GameMap, player_map: &mut HashMap<u8, Player>, packs: &mut MiniPacks,
+	pub fn server_force_stage(&mut self, stage: GameStage, map: &GameMap, player_map: &mut HashMap<u8, Player>,
+		packs: &mut MiniPacks, player_pack_counts: &mut HashMap<u8, usize>) -> Option<AppMessage> {
+		self.last_update_time = Instant::now();
+		self.forced_start = true;
+		let update = match stage {
+			GameStage::Standby | GameStage::Lobby | GameStage::Warmup => {
+				self.series.reset();
+				for player in player_map.values_mut() {
+					player.respawn();
+				}
+				self.players_respawned = true;
+				self.server_warmup(player_map, packs, player_pack_counts)
+			},
+			GameStage::InProgress => self.server_start_game(map, player_map, packs, player_pack_counts),
+			GameStage::Overtime => {
+				let mut tied = tied_leaders(&self.mode.scores(&packs.wolf_counts()), &self.teams);
+				if tied.is_empty() {
+					tied = player_map.keys().cloned().collect();
+				}
+				self.server_overtime(tied, packs)
+			},
+			GameStage::Finished => {
+				let winner = leading_player(&self.mode.scores(&packs.wolf_counts()), &self.teams);
+				self.server_finish_game(winner, player_map, packs)
+			}
+		};
+
+		update.map(|u| AppMessage::StageChange(u))
+	}
+
+	pub fn server_update(&mut self, map: &GameMap, nav: &NavGrid, player_map: &mut HashMap<u8, Player>, packs: &mut MiniPacks,
 		player_pack_counts: &mut HashMap<u8, usize>) -> Option<AppMessage> {
 
+		let in_game = match self.current_stage {
+			GameStage::InProgress | GameStage::Overtime => true,
+			_ => false
+		};
+		if in_game && self.mode.uses_elimination() {
+			self.update_eliminations(player_pack_counts);
+		}
+
+		if let (GameStage::Overtime, Some(winner)) = (&self.current_stage, self.overtime_winner) {
+			info!("player {} won in overtime", winner);
+			self.last_update_time = Instant::now();
+			return self.server_finish_game(Some(winner), player_map, packs).map(|u| AppMessage::StageChange(u));
+		}
+
 		if self.last_update_time.elapsed().as_secs() < 1 {
 			return None;
 		}
@@ -75,49 +468,112 @@ impl GameStageManager {
 		let update = match self.current_stage {
 			GameStage::Standby => {
 				if !player_map.is_empty() {
-					self.current_stage = GameStage::Warmup;
-					self.stage_start_time = Instant::now();
-					Some(GameStageUpdate::Warmup { time_remaining: WARMUP_SECONDS as u8 })
+					self.server_lobby(player_map, packs, player_pack_counts)
 				} else {
 					None
 				}
 			},
+			GameStage::Lobby => self.server_lobby(player_map, packs, player_pack_counts),
 			GameStage::Warmup => {
-				if elapsed_secs >= WARMUP_SECONDS {
+				if !self.forced_start && self.ready_count(player_map) < self.rules.min_players {
+					self.server_lobby(player_map, packs, player_pack_counts)
+				} else if elapsed_secs >= self.rules.warmup_seconds {
 					self.server_start_game(map, player_map, packs, player_pack_counts)
 				} else {
 					Some(GameStageUpdate::Warmup { 
-						time_remaining: (WARMUP_SECONDS as u8) - (elapsed_secs as u8)
+						time_remaining: (self.rules.warmup_seconds - elapsed_secs) as u16,
+						round: self.series.round_info(&self.rules)
 					})
 				}
 			},
 			GameStage::InProgress => {
-				if (elapsed_secs > EARLY_FINISH_SECONDS && player_pack_counts.values().filter(|v| **v > 0).count() == 1) ||
-					elapsed_secs >= GAME_SECONDS {
-					
-					self.current_stage = GameStage::Finished;
-					self.stage_start_time = Instant::now();
-					Some(GameStageUpdate::Finished)
+				let zone = self.zone_state.as_ref().map(|z| z.at(elapsed_secs as f32, &self.rules.zone));
+				self.server_zone_update(zone, player_map, packs, nav, player_pack_counts);
+				let ctx = ModeContext {
+					map: map,
+					player_map: player_map,
+					packs: packs,
+					player_pack_counts: player_pack_counts,
+					rules: &self.rules,
+					teams: &self.teams,
+					eliminated: &self.eliminated
+				};
+				self.mode.tick(&ctx, elapsed_secs);
+				match self.mode.end_condition(&ctx, elapsed_secs) {
+					Some(end) if end.winner.is_none() && end.tied.len() >= 2 && self.rules.overtime.enabled => {
+						self.server_overtime(end.tied, packs)
+					},
+					Some(end) => self.server_finish_game(end.winner, player_map, packs),
+					None => Some(GameStageUpdate::InProgress {
+						time_remaining: self.rules.game_seconds.saturating_sub(elapsed_secs) as u16,
+						eliminated: self.eliminated_list(),
+						zone: self.zone,
+						scores: self.score_list(packs),
+						hill: self.mode.hill(),
+						round: self.series.round_info(&self.rules)
+					})
+				}
+			},
+			GameStage::Overtime => {
+				let zone = self.overtime_zone(elapsed_secs);
+				self.server_zone_update(zone, player_map, packs, nav, player_pack_counts);
+				// the mode keeps running, counting time on from the end of the regular game
+				let game_secs = self.rules.game_seconds + elapsed_secs;
+				let ctx = ModeContext {
+					map: map,
+					player_map: player_map,
+					packs: packs,
+					player_pack_counts: player_pack_counts,
+					rules: &self.rules,
+					teams: &self.teams,
+					eliminated: &self.eliminated
+				};
+				self.mode.tick(&ctx, game_secs);
+				// only the tied players can still win, by ending the game or pulling ahead of each other
+				let winner = self.mode.end_condition(&ctx, game_secs)
+					.and_then(|end| end.winner)
+					.filter(|winner| self.is_overtime_side(*winner))
+					.or_else(|| {
+						let tied_scores: HashMap<u8, u32> = self.mode.scores(&packs.wolf_counts()).into_iter()
+							.filter(|(pid, _)| self.is_overtime_side(*pid))
+							.collect();
+						leading_player(&tied_scores, &self.teams)
+					});
+				if let Some(winner) = winner {
+					info!("player {} won in overtime", winner);
+					self.server_finish_game(Some(winner), player_map, packs)
+				} else if elapsed_secs >= self.rules.overtime.max_seconds {
+					info!("overtime ended in a tie");
+					self.server_finish_game(None, player_map, packs)
 				} else {
-					Some(GameStageUpdate::InProgress { 
-						time_remaining: (GAME_SECONDS as u16) - (elapsed_secs as u16)
+					Some(GameStageUpdate::Overtime {
+						time_remaining: (self.rules.overtime.max_seconds - elapsed_secs) as u16,
+						tied: self.overtime_tied.clone(),
+						eliminated: self.eliminated_list(),
+						zone: self.zone,
+						scores: self.score_list(packs),
+						hill: self.mode.hill(),
+						round: self.series.round_info(&self.rules)
 					})
 				}
 			},
 			GameStage::Finished => {
-				if elapsed_secs >= FINISH_SECONDS {
-					self.stage_start_time = Instant::now();
-					packs.packs.clear();
-					player_pack_counts.clear();
-					if !player_map.is_empty() {
-						self.current_stage = GameStage::Warmup;
-						Some(GameStageUpdate::Warmup { time_remaining: WARMUP_SECONDS as u8 })
-					} else {
-						self.current_stage = GameStage::Standby;
-						None
-					}
-				} else {
+				if elapsed_secs < self.rules.finish_seconds {
 					None
+				} else if self.series.is_over() || !self.series.round_info(&self.rules).is_series() {
+					// players ready up again for the next match, but stay ready between rounds of a series
+					self.series.reset();
+					self.ready_pids.clear();
+					self.server_lobby(player_map, packs, player_pack_counts)
+				} else {
+					self.series.next_round();
+					// only rotate maps between rounds, so a map picked from the console is kept for the first one
+					self.pending_map = self.series.round_map(&self.rules);
+					for player in player_map.values_mut() {
+						player.respawn();
+					}
+					self.players_respawned = true;
+					self.server_warmup(player_map, packs, player_pack_counts)
 				}
 			}
 		};
@@ -129,31 +585,113 @@ impl GameStageManager {
 		format!("{:0>2}:{:0>2}", seconds / 60, seconds % 60)
 	}
 
-	pub fn client_update(&mut self, update: GameStageUpdate, map: &GameMap, packs: &mut MiniPacks) {
+	fn format_round(round: &RoundInfo) -> String {
+		format!("Round {}/{}", round.round, round.rounds)
+	}
+
+	fn round_text(round: &RoundInfo) -> Option<FontText> {
+		if !round.is_series() {
+			return None;
+		}
+		let mut text = FontText::new(Self::format_round(round), ROUND_TEXT_SIZE, (0., 0.95), TextAlign::Center);
+		text.ui_draw_info.color = [1.0, 1.0, 1.0, 1.0];
+		Some(text)
+	}
+
+	/// State shared by the in progress and overtime updates
+	fn client_match_update(&mut self, eliminated: Vec<u8>, zone: Option<SafeZone>, scores: Vec<(u8, u32)>,
+		hill: Option<Hill>, player_id: Option<u8>) {
+		self.scores = scores.into_iter().collect();
+		self.eliminated.extend(eliminated);
+		self.zone = zone;
+		self.hill = hill;
+		if player_id.map(|pid| self.eliminated.contains(&pid)).unwrap_or(false) {
+			let mut detail_text = FontText::new("Eliminated - spectating".to_string(), LOBBY_TEXT_SIZE,
+				(0., 0.7), TextAlign::Center);
+			detail_text.ui_draw_info.color = [1.0, 1.0, 1.0, 1.0];
+			self.detail_text = Some(detail_text);
+		}
+	}
+
+	pub fn client_update(&mut self, update: GameStageUpdate, map: &GameMap, packs: &mut MiniPacks,
+		peers: &HashMap<u8, PeerMeta>, player_id: Option<u8>) {
+		self.detail_text = None;
+		self.round_text = None;
+		self.results = None;
+		self.zone = None;
+		self.hill = None;
 		match update {
-			GameStageUpdate::Warmup { time_remaining } => {
-				if let GameStage::Finished = self.current_stage {
-					packs.packs.clear();
-				}
-				self.current_stage = GameStage::Warmup;
-				self.text = Some(FontText::new(format!("Warmup {}", Self::format_time(time_remaining as usize)),
+			GameStageUpdate::Lobby { ready_pids, min_players } => {
+				match self.current_stage {
+					GameStage::Lobby => (),
+					_ => packs.packs.clear()
+				};
+				self.current_stage = GameStage::Lobby;
+				self.text = Some(FontText::new(format!("Waiting for players {}/{} ready", ready_pids.len(), min_players),
 					WARMUP_TEXT_SIZE, (0., 0.3), TextAlign::Center));
+				let ready_names: Vec<String> = ready_pids.iter()
+					.filter_map(|pid| peers.get(pid).and_then(|p| p.name.clone()))
+					.collect();
+				let mut detail_text = FontText::new(if ready_names.is_empty() {
+					"Press R to ready up".to_string()
+				} else {
+					format!("Ready: {} - press R to toggle", ready_names.join(", "))
+				}, LOBBY_TEXT_SIZE, (0., 0.15), TextAlign::Center);
+				detail_text.ui_draw_info.color = [1.0, 1.0, 1.0, 1.0];
+				self.detail_text = Some(detail_text);
 			},
-			GameStageUpdate::InProgress { time_remaining } => {
+			GameStageUpdate::Warmup { time_remaining, round } => {
+				match self.current_stage {
+					GameStage::Warmup => (),
+					_ => packs.packs.clear()
+				};
+				self.current_stage = GameStage::Warmup;
+				let mut content = format!("{} warmup {}", self.rules.mode.name(), Self::format_time(time_remaining as usize));
+				if round.is_series() {
+					content = format!("{} - {}", Self::format_round(&round), content);
+				}
+				self.text = Some(FontText::new(content, WARMUP_TEXT_SIZE, (0., 0.3), TextAlign::Center));
+			},
+			GameStageUpdate::InProgress { time_remaining, eliminated, zone, scores, hill, round } => {
+				match self.current_stage {
+					GameStage::InProgress => (),
+					_ => self.eliminated.clear()
+				};
 				if packs.packs.is_empty() {
 					packs.spawn(map);
 				}
 				self.current_stage = GameStage::InProgress;
 				self.text = Some(FontText::new(Self::format_time(time_remaining as usize), MAIN_TEXT_SIZE,
 					(0., 0.83), TextAlign::Center));
+				self.round_text = Self::round_text(&round);
+				self.client_match_update(eliminated, zone, scores, hill, player_id);
+			},
+			GameStageUpdate::Overtime { time_remaining, tied, eliminated, zone, scores, hill, round } => {
+				self.current_stage = GameStage::Overtime;
+				self.text = Some(FontText::new(format!("Overtime {}", Self::format_time(time_remaining as usize)),
+					MAIN_TEXT_SIZE, (0., 0.83), TextAlign::Center));
+				let tied_names: Vec<String> = tied.iter()
+					.filter_map(|pid| peers.get(pid).and_then(|p| p.name.clone()))
+					.collect();
+				let mut detail_text = FontText::new(format!("Sudden death: next capture or steal by {} wins",
+					tied_names.join(", ")), LOBBY_TEXT_SIZE, (0., 0.7), TextAlign::Center);
+				detail_text.ui_draw_info.color = [1.0, 1.0, 1.0, 1.0];
+				self.detail_text = Some(detail_text);
+				self.round_text = Self::round_text(&round);
+				self.client_match_update(eliminated, zone, scores, hill, player_id);
 			},
-			GameStageUpdate::Finished => {
+			GameStageUpdate::Finished { winner, winning_team, standings, series } => {
 				self.current_stage = GameStage::Finished;
-				self.text = Some(FontText::new("Game Over".to_string(), MAIN_TEXT_SIZE,
-					(0., 0.1), TextAlign::Center));
+				self.eliminated.clear();
+				self.scores.clear();
+				self.text = None;
+				self.results = Some(ResultsScreen::new(winner, winning_team, &standings, series.as_ref(), &self.teams,
+					self.rules.mode.uses_points()));
 			}
 		};
-		self.text.as_mut().unwrap().ui_draw_info.color = [1.0, 0.25, 0.25, 1.0];
+		if let Some(text) = self.text.as_mut() {
+			text.ui_draw_info.color = [1.0, 0.25, 0.25, 1.0];
+		}
 	}
 
 	pub fn draw(&mut self, target: &mut Frame, display: &Display, program: &Program,
@@ -161,6 +699,15 @@ impl GameStageManager {
 		if let Some(text) = self.text.as_mut() {
 			text.draw(target, display, program, font)?;
 		}
+		if let Some(text) = self.detail_text.as_mut() {
+			text.draw(target, display, program, font)?;
+		}
+		if let Some(text) = self.round_text.as_mut() {
+			text.draw(target, display, program, font)?;
+		}
+		if let Some(results) = self.results.as_mut() {
+			results.draw(target, display, program, font)?;
+		}
 
 		Ok(())
 	}
@@ -0,0 +1,80 @@
+use serde::{Serialize, Deserialize};
+use std::collections::HashMap;
+use crate::constants::{player_color, TEAM_COLORS, TEAM_NAMES};
+
+#[derive(Serialize, Deserialize, Clone, Default)]
+pub struct Teams {
+	team_count: u8,
+	assignments: HashMap<u8, u8>
+}
+
+impl Teams {
+	pub fn new(team_count: u8) -> Self {
+		Self {
+			team_count: team_count.min(TEAM_COLORS.len() as u8),
+			assignments: HashMap::new()
+		}
+	}
+
+	pub fn enabled(&self) -> bool {
+		self.team_count > 1
+	}
+
+	pub fn team_count(&self) -> u8 {
+		self.team_count
+	}
+
+	/// Puts a new player on the team with the fewest members.
+	pub fn assign(&mut self, pid: u8) {
+		if !self.enabled() || self.assignments.contains_key(&pid) { return; }
+		let team = (0..self.team_count)
+			.min_by_key(|team| self.assignments.values().filter(|v| *v == team).count())
+			.unwrap_or(0);
+		self.assignments.insert(pid, team);
+	}
+
+	pub fn remove(&mut self, pid: u8) -> bool {
+		self.assignments.remove(&pid).is_some()
+	}
+
+	pub fn team_of(&self, pid: u8) -> Option<u8> {
+		self.assignments.get(&pid).cloned()
+	}
+
+	pub fn are_teammates(&self, a: u8, b: u8) -> bool {
+		match (self.team_of(a), self.team_of(b)) {
+			(Some(a_team), Some(b_team)) => a_team == b_team,
+			_ => a == b
+		}
+	}
+
+	pub fn members(&self, team: u8) -> Vec<u8> {
+		let mut members: Vec<u8> = self.assignments.iter().filter(|(_, v)| **v == team).map(|(k, _)| *k).collect();
+		members.sort();
+		members
+	}
+
+	/// Total count of the player's team, or of the player alone when teams are disabled.
+	/// Works with both per-player pack and wolf counts.
+	pub fn side_total(&self, pid: u8, player_counts: &HashMap<u8, usize>) -> usize {
+		match self.team_of(pid) {
+			Some(team) => self.team_total(team, player_counts),
+			None => *player_counts.get(&pid).unwrap_or(&0)
+		}
+	}
+
+	pub fn team_total(&self, team: u8, player_counts: &HashMap<u8, usize>) -> usize {
+		self.members(team).iter().map(|pid| player_counts.get(pid).unwrap_or(&0)).sum()
+	}
+
+	pub fn color(&self, pid: u8) -> [f32; 3] {
+		match self.team_of(pid) {
+			Some(team) => TEAM_COLORS[team as usize],
+			None => *player_color(pid)
+		}
+	}
+
+	pub fn team_name(team: u8) -> &'static str {
+		TEAM_NAMES[team as usize]
+	}
+}
@@ -0,0 +1,59 @@
+use std::time::{Duration, Instant};
+use std::thread::sleep;
+use log::warn;
+
+pub struct TickScheduler {
+	tick_duration: Duration,
+	max_catch_up_ticks: u32,
+	tick_start_time: Instant,
+	next_tick_time: Instant
+}
+
+impl TickScheduler {
+	pub fn new(tick_rate: u32, max_catch_up_ticks: u32) -> Self {
+		let now = Instant::now();
+		let tick_duration = Duration::from_secs_f64(1. / tick_rate.max(1) as f64);
+		Self {
+			tick_duration: tick_duration,
+			max_catch_up_ticks: max_catch_up_ticks,
+			tick_start_time: now,
+			next_tick_time: now + tick_duration
+		}
+	}
+
+	/// Fixed time delta of every simulated tick.
+	pub fn dt(&self) -> f32 {
+		self.tick_duration.as_secs_f32()
+	}
+
+	/// Time spent working on the current tick.
+	pub fn tick_elapsed(&self) -> Duration {
+		self.tick_start_time.elapsed()
+	}
+
+	/// Ends the current tick, and sleeps until the next one is due.
+	/// If the server has fallen behind, ticks are run back to back until it catches up,
+	/// unless it is more than `max_catch_up_ticks` behind, in which case the missed ticks are dropped.
+	pub fn wait(&mut self) {
+		let work_time = self.tick_elapsed();
+		if work_time > self.tick_duration {
+			warn!("tick overran by {:.2} ms ({:.2} ms of work)",
+				(work_time - self.tick_duration).as_secs_f64() * 1000., work_time.as_secs_f64() * 1000.);
+		}
+
+		let now = Instant::now();
+		if now < self.next_tick_time {
+			sleep(self.next_tick_time - now);
+		} else {
+			let behind = now - self.next_tick_time;
+			let ticks_behind = (behind.as_secs_f64() / self.tick_duration.as_secs_f64()) as u32;
+			if ticks_behind > self.max_catch_up_ticks {
+				warn!("server fell {} ticks behind, dropping them", ticks_behind);
+				self.next_tick_time = now;
+			}
+		}
+
+		self.tick_start_time = Instant::now();
+		self.next_tick_time += self.tick_duration;
+	}
+}
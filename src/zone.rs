@@ -0,0 +1,124 @@
+use cubik::map::GameMap;
+use serde::{Serialize, Deserialize};
+
+const CENTER_MISC_OBJ: &str = "misc_zone_center";
+const RADIUS_MARGIN: f32 = 5.;
+
+#[derive(Serialize, Deserialize, Clone)]
+pub struct ZonePhase {
+	pub wait_seconds: f32,
+	pub shrink_seconds: f32,
+	/// Radius after shrinking, relative to the starting radius
+	pub radius_fraction: f32
+}
+
+#[derive(Serialize, Deserialize, Clone)]
+#[serde(default)]
+pub struct ZoneRules {
+	pub enabled: bool,
+	/// Seconds a player can spend outside the zone before losing a pack
+	pub pack_loss_seconds: f32,
+	/// If true, lost packs are scattered away from the player instead of dropped in place
+	pub scatter_lost_packs: bool,
+	pub phases: Vec<ZonePhase>
+}
+
+impl Default for ZoneRules {
+	fn default() -> Self {
+		Self {
+			enabled: true,
+			pack_loss_seconds: 3.,
+			scatter_lost_packs: true,
+			phases: vec![
+				ZonePhase { wait_seconds: 30., shrink_seconds: 15., radius_fraction: 0.6 },
+				ZonePhase { wait_seconds: 20., shrink_seconds: 15., radius_fraction: 0.3 },
+				ZonePhase { wait_seconds: 15., shrink_seconds: 10., radius_fraction: 0.1 }
+			]
+		}
+	}
+}
+
+#[derive(Serialize, Deserialize, Clone, Copy)]
+pub struct SafeZone {
+	pub center: (f32, f32),
+	pub radius: f32,
+	pub target_radius: f32,
+	pub seconds_to_shrink: u16,
+	pub shrinking: bool
+}
+
+impl SafeZone {
+	pub fn contains(&self, position: &[f32; 3]) -> bool {
+		let x = position[0] - self.center.0;
+		let z = position[2] - self.center.1;
+		(x * x + z * z).sqrt() <= self.radius
+	}
+}
+
+/// Server side zone origin, from which the zone at any point in the match is derived
+pub struct ZoneState {
+	center: (f32, f32),
+	initial_radius: f32
+}
+
+impl ZoneState {
+	pub fn for_map(map: &GameMap) -> Self {
+		let points: Vec<(f32, f32)> = map.misc_objs.values().map(|v| (v[0], v[2])).collect();
+		let center = match map.misc_objs.get(CENTER_MISC_OBJ) {
+			Some(v) => (v[0], v[2]),
+			None if !points.is_empty() => {
+				let sum = points.iter().fold((0., 0.), |acc, p| (acc.0 + p.0, acc.1 + p.1));
+				(sum.0 / points.len() as f32, sum.1 / points.len() as f32)
+			},
+			None => (0., 0.)
+		};
+		let max_distance = points.iter()
+			.map(|p| ((p.0 - center.0).powi(2) + (p.1 - center.1).powi(2)).sqrt())
+			.fold(0., f32::max);
+		Self {
+			center: center,
+			initial_radius: max_distance + RADIUS_MARGIN
+		}
+	}
+
+	pub fn center(&self) -> (f32, f32) {
+		self.center
+	}
+
+	pub fn at(&self, elapsed_secs: f32, rules: &ZoneRules) -> SafeZone {
+		let mut remaining = elapsed_secs;
+		let mut radius = self.initial_radius;
+		for phase in &rules.phases {
+			let target_radius = self.initial_radius * phase.radius_fraction;
+			if remaining < phase.wait_seconds {
+				return SafeZone {
+					center: self.center,
+					radius: radius,
+					target_radius: target_radius,
+					seconds_to_shrink: (phase.wait_seconds - remaining).ceil() as u16,
+					shrinking: false
+				};
+			}
+			remaining -= phase.wait_seconds;
+			if remaining < phase.shrink_seconds {
+				let progress = remaining / phase.shrink_seconds;
+				return SafeZone {
+					center: self.center,
+					radius: radius + (target_radius - radius) * progress,
+					target_radius: target_radius,
+					seconds_to_shrink: 0,
+					shrinking: true
+				};
+			}
+			remaining -= phase.shrink_seconds;
+			radius = target_radius;
+		}
+		SafeZone {
+			center: self.center,
+			radius: radius,
+			target_radius: radius,
+			seconds_to_shrink: 0,
+			shrinking: false
+		}
+	}
+}